@@ -1,8 +1,11 @@
 use anyhow::{bail, Context as _};
 use indicatif::ProgressDrawTarget;
 use maplit::btreemap;
-use snowchains_core::{judge::CommandExpression, testsuite::TestSuite};
-use std::{env, ffi::OsString, fs, path::PathBuf};
+use snowchains_core::{
+    judge::{CommandExpression, JudgeOptions, ResourceLimits},
+    testsuite::TestSuite,
+};
+use std::{collections::HashSet, env, ffi::OsString, fs, num::NonZeroUsize, path::PathBuf};
 use structopt::StructOpt;
 use termcolor::BufferedStandardStream;
 
@@ -11,6 +14,15 @@ struct Opt {
     #[structopt(long, value_name("NAME"))]
     testcases: Option<Vec<String>>,
 
+    #[structopt(long)]
+    cpu_time: bool,
+
+    #[structopt(long, value_name("N"))]
+    jobs: Option<NonZeroUsize>,
+
+    #[structopt(long)]
+    fail_fast: bool,
+
     file: PathBuf,
 
     #[structopt(parse(from_os_str), raw(true), required(true))]
@@ -20,6 +32,9 @@ struct Opt {
 fn main() -> anyhow::Result<()> {
     let Opt {
         testcases,
+        cpu_time,
+        jobs,
+        fail_fast,
         file,
         args,
     } = Opt::from_args();
@@ -33,15 +48,22 @@ fn main() -> anyhow::Result<()> {
     let test_suite = serde_yaml::from_slice(&test_suite)
         .with_context(|| format!("Could not parse the test file at `{}`", file.display()))?;
 
-    let test_suite = match test_suite {
-        TestSuite::Batch(test_suite) => test_suite,
-        _ => bail!("Only `Batch` is supported"),
-    };
+    let parent_dir = file.parent().expect("should have file name");
+    let testcases = testcases.map(|ss| ss.into_iter().collect::<HashSet<_>>());
 
-    let test_cases = test_suite.load_test_cases(
-        file.parent().expect("should have file name"),
-        testcases.map(|ss| ss.into_iter().collect()),
-    )?;
+    let test_cases = match test_suite {
+        TestSuite::Batch(test_suite) => test_suite
+            .load_test_cases(parent_dir, testcases)?
+            .into_iter()
+            .map(Into::into)
+            .collect(),
+        TestSuite::Interactive(test_suite) => test_suite
+            .load_test_cases(parent_dir, testcases)?
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<_>>(),
+        TestSuite::Unsubmittable => bail!("The problem is unsubmittable"),
+    };
 
     let outcome = snowchains_core::judge::judge(
         ProgressDrawTarget::stderr(),
@@ -51,8 +73,17 @@ fn main() -> anyhow::Result<()> {
             args: args[1..].to_owned(),
             cwd,
             env: btreemap!(),
+            resource_limits: ResourceLimits::default(),
         },
         &test_cases,
+        JudgeOptions {
+            timelimit_on_cpu_time: cpu_time,
+            output_limit: None,
+            jobs,
+            fail_fast,
+            sandbox: None,
+            compact_progress_above: None,
+        },
     )?;
 
     let stdout = BufferedStandardStream::stdout(if atty::is(atty::Stream::Stdout) {
@@ -62,7 +93,7 @@ fn main() -> anyhow::Result<()> {
     });
 
     eprintln!();
-    outcome.print_pretty(stdout, None)?;
+    outcome.print_pretty(stdout, None, None)?;
 
     Ok(())
 }
@@ -0,0 +1,253 @@
+use super::{JudgeOutcome, Verdict};
+use crate::testsuite::TestCase;
+use prettytable::{
+    cell,
+    format::{FormatBuilder, LinePosition, LineSeparator},
+    row, Table,
+};
+use serde::{Serialize, Serializer};
+use std::{fmt, time::Duration};
+
+/// Results of running every test case several times.
+#[derive(Debug, Clone)]
+pub struct BenchmarkOutcome {
+    pub cases: Vec<BenchmarkCase>,
+    /// A case is flagged if its slowest run takes more than this fraction of `timelimit`.
+    pub near_timelimit: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkCase {
+    pub test_case_name: Option<String>,
+    pub timelimit: Option<Duration>,
+    /// One for each run.
+    pub verdicts: Vec<Verdict>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ElapsedStats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+    pub stddev: Duration,
+}
+
+impl BenchmarkOutcome {
+    /// Groups the verdicts of `runs` by test case.
+    pub fn new(test_cases: &[TestCase], runs: &[JudgeOutcome], near_timelimit: f64) -> Self {
+        let cases = test_cases
+            .iter()
+            .enumerate()
+            .map(|(i, test_case)| BenchmarkCase {
+                test_case_name: test_case.name().map(ToOwned::to_owned),
+                timelimit: test_case.timelimit(),
+                verdicts: runs
+                    .iter()
+                    .flat_map(|run| run.verdicts.get(i).cloned())
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            cases,
+            near_timelimit,
+        }
+    }
+
+    /// Takes the first failure of each test case, or the first run if all of the runs passed.
+    pub fn merge(&self) -> JudgeOutcome {
+        let verdicts = self
+            .cases
+            .iter()
+            .flat_map(|case| {
+                case.verdicts
+                    .iter()
+                    .find(|v| !matches!(v, Verdict::Accepted { .. } | Verdict::Skipped { .. }))
+                    .or_else(|| case.verdicts.first())
+                    .cloned()
+            })
+            .collect();
+
+        JudgeOutcome { verdicts }
+    }
+
+    pub fn to_table(&self) -> impl fmt::Display {
+        let mut table = Table::new();
+
+        *table.get_format() = FormatBuilder::new()
+            .padding(1, 1)
+            .column_separator('│')
+            .borders('│')
+            .separator(LinePosition::Top, LineSeparator::new('─', '┬', '┌', '┐'))
+            .separator(LinePosition::Title, LineSeparator::new('─', '┼', '├', '┤'))
+            .separator(LinePosition::Intern, LineSeparator::new('─', '┼', '├', '┤'))
+            .separator(LinePosition::Bottom, LineSeparator::new('─', '┴', '└', '┘'))
+            .build();
+
+        table.set_titles(row![
+            "Name", "Runs", "Min", "Median", "Max", "Stddev", "Note"
+        ]);
+
+        let millis = |d: Duration| format!("{} ms", d.as_millis());
+
+        for case in &self.cases {
+            let name = format!("{:?}", case.test_case_name.as_deref().unwrap_or(""));
+            let runs = case.verdicts.len();
+
+            let note = match case.timelimit {
+                Some(timelimit) if self.is_near_timelimit(case) => format!(
+                    "> {}% of {} ms",
+                    (self.near_timelimit * 100.0).round(),
+                    timelimit.as_millis(),
+                ),
+                _ => "".to_owned(),
+            };
+
+            let note = match case.failures() {
+                0 => note,
+                n if note.is_empty() => format!("{} failed", n),
+                n => format!("{}, {} failed", note, n),
+            };
+
+            if let Some(stats) = case.stats() {
+                table.add_row(row![
+                    name,
+                    r->runs,
+                    r->millis(stats.min),
+                    r->millis(stats.median),
+                    r->millis(stats.max),
+                    r->format!("{:.1} ms", stats.stddev.as_secs_f64() * 1000.0),
+                    note,
+                ]);
+            } else {
+                table.add_row(row![name, r->runs, "-", "-", "-", "-", note]);
+            }
+        }
+
+        table
+    }
+
+    /// Whether the slowest run of `case` is close to or over `timelimit`.
+    pub fn is_near_timelimit(&self, case: &BenchmarkCase) -> bool {
+        let timelimit = match case.timelimit {
+            Some(timelimit) => timelimit,
+            None => return false,
+        };
+
+        case.verdicts
+            .iter()
+            .any(|v| matches!(v, Verdict::TimelimitExceeded { .. }))
+            || case
+                .stats()
+                .map_or(false, |s| s.max > timelimit.mul_f64(self.near_timelimit))
+    }
+}
+
+impl Serialize for BenchmarkOutcome {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct BenchmarkCaseJson<'a> {
+            test_case_name: Option<&'a str>,
+            runs: usize,
+            failures: usize,
+            timelimit_ms: Option<u64>,
+            min_ms: Option<f64>,
+            median_ms: Option<f64>,
+            max_ms: Option<f64>,
+            stddev_ms: Option<f64>,
+            near_timelimit: bool,
+        }
+
+        let millis = |d: Duration| d.as_secs_f64() * 1000.0;
+
+        serializer.collect_seq(self.cases.iter().map(|case| {
+            let stats = case.stats();
+
+            BenchmarkCaseJson {
+                test_case_name: case.test_case_name.as_deref(),
+                runs: case.verdicts.len(),
+                failures: case.failures(),
+                timelimit_ms: case.timelimit.map(|t| t.as_millis() as u64),
+                min_ms: stats.map(|s| millis(s.min)),
+                median_ms: stats.map(|s| millis(s.median)),
+                max_ms: stats.map(|s| millis(s.max)),
+                stddev_ms: stats.map(|s| millis(s.stddev)),
+                near_timelimit: self.is_near_timelimit(case),
+            }
+        }))
+    }
+}
+
+impl BenchmarkCase {
+    /// Statistics of the runs that finished. Returns `None` if none of them did.
+    pub fn stats(&self) -> Option<ElapsedStats> {
+        ElapsedStats::new(self.verdicts.iter().flat_map(Verdict::elapsed).collect())
+    }
+
+    fn failures(&self) -> usize {
+        self.verdicts
+            .iter()
+            .filter(|v| !matches!(v, Verdict::Accepted { .. } | Verdict::Skipped { .. }))
+            .count()
+    }
+}
+
+impl ElapsedStats {
+    fn new(mut elapsed: Vec<Duration>) -> Option<Self> {
+        if elapsed.is_empty() {
+            return None;
+        }
+
+        elapsed.sort();
+
+        let n = elapsed.len();
+
+        let median = if n % 2 == 1 {
+            elapsed[n / 2]
+        } else {
+            (elapsed[n / 2 - 1] + elapsed[n / 2]) / 2
+        };
+
+        let mean = elapsed.iter().map(Duration::as_secs_f64).sum::<f64>() / n as f64;
+
+        let variance = elapsed
+            .iter()
+            .map(|d| (d.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / n as f64;
+
+        Some(Self {
+            min: elapsed[0],
+            median,
+            max: elapsed[n - 1],
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ElapsedStats;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn elapsed_stats() {
+        let ms = Duration::from_millis;
+
+        assert_eq!(None, ElapsedStats::new(vec![]));
+
+        let stats = ElapsedStats::new(vec![ms(40), ms(10), ms(20), ms(30)]).unwrap();
+        assert_eq!(
+            (ms(10), ms(25), ms(40)),
+            (stats.min, stats.median, stats.max),
+        );
+
+        let stats = ElapsedStats::new(vec![ms(10), ms(30), ms(20)]).unwrap();
+        assert_eq!(ms(20), stats.median);
+        assert!((stats.stddev.as_secs_f64() - 0.00816496).abs() < 1e-7);
+    }
+}
@@ -0,0 +1,288 @@
+use super::ResourceLimits;
+use std::process::ExitStatus;
+
+/// Returns the signal that killed the process.
+///
+/// Exit codes above 128 are not decoded since a solution may return them by itself.
+pub(super) fn signal(status: ExitStatus) -> Option<i32> {
+    #[cfg(unix)]
+    {
+        std::os::unix::process::ExitStatusExt::signal(&status)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = status;
+        None
+    }
+}
+
+/// Formats `status` with the name of the signal, e.g. `signal: 11 (SIGSEGV)`.
+pub(super) fn describe_status(status: ExitStatus) -> String {
+    match signal(status).and_then(signal_name) {
+        Some(name) => format!("{} ({})", status, name),
+        None => status.to_string(),
+    }
+}
+
+/// Returns the soft stack size limit the process runs with, or `None` if it is unlimited.
+///
+/// The process inherits our own limit unless `limits` sets one, which is clamped to the hard
+/// limit.
+pub(super) fn stack_size(limits: &ResourceLimits) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+
+        if unsafe { libc::getrlimit(libc::RLIMIT_STACK, &mut limit) } == -1 {
+            return limits.stack_size;
+        }
+
+        let bounded = |l: libc::rlim_t| Some(l as u64).filter(|_| l != libc::RLIM_INFINITY);
+
+        match limits.stack_size {
+            Some(size) => Some(bounded(limit.rlim_max).map_or(size, |max| size.min(max))),
+            None => bounded(limit.rlim_cur),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        limits.stack_size
+    }
+}
+
+/// Explains why the process crashed, with summaries of the sanitizer reports in `stderr`.
+///
+/// `stack_size` is the stack size limit the process ran with.
+pub(super) fn diagnose(
+    status: Option<ExitStatus>,
+    stderr: &str,
+    stack_size: Option<u64>,
+) -> Vec<String> {
+    let mut notes = sanitizer_reports(stderr);
+
+    let fault = fault_address(stderr);
+
+    let stack_overflow = [
+        "AddressSanitizer: stack-overflow",
+        "has overflowed its stack",
+    ]
+    .iter()
+    .any(|p| stderr.contains(p))
+        || fault.map_or(false, FaultAddress::is_near_stack_pointer);
+
+    if stack_overflow {
+        notes.push(format!(
+            "Likely a stack overflow{}",
+            stack_limit_note(stack_size),
+        ));
+    } else if fault.map_or(false, |f| f.addr < 4096) {
+        notes.push("Likely a null pointer dereference".to_owned());
+    } else if let Some(hint) = status
+        .and_then(signal)
+        .and_then(|s| signal_hint(s, stack_size))
+    {
+        notes.push(hint);
+    }
+
+    notes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FaultAddress {
+    addr: u64,
+    sp: Option<u64>,
+}
+
+impl FaultAddress {
+    /// A fault right below the stack pointer means a function call ran into the guard page.
+    fn is_near_stack_pointer(self) -> bool {
+        self.sp.map_or(false, |sp| {
+            self.addr <= sp.saturating_add(4096) && sp.saturating_sub(self.addr) < 64 * 1024
+        })
+    }
+}
+
+/// Finds the fault address in a report of AddressSanitizer, e.g.
+/// `SEGV on unknown address 0x7ffd3f8aeff8 (pc 0x55d0 bp 0x7ffd3f8af010 sp 0x7ffd3f8aeff0 T0)`.
+fn fault_address(stderr: &str) -> Option<FaultAddress> {
+    let hex = |s: &str| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok();
+
+    let line = stderr
+        .lines()
+        .find(|l| l.contains("AddressSanitizer: SEGV on unknown address "))?;
+    let rest = line.split("on unknown address ").nth(1)?;
+    let addr = hex(rest.split_whitespace().next()?)?;
+    let sp = rest
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .skip_while(|&w| w != "sp")
+        .nth(1)
+        .and_then(hex);
+
+    Some(FaultAddress { addr, sp })
+}
+
+fn signal_name(signal: i32) -> Option<&'static str> {
+    #[cfg(unix)]
+    {
+        Some(match signal {
+            libc::SIGHUP => "SIGHUP",
+            libc::SIGINT => "SIGINT",
+            libc::SIGQUIT => "SIGQUIT",
+            libc::SIGILL => "SIGILL",
+            libc::SIGTRAP => "SIGTRAP",
+            libc::SIGABRT => "SIGABRT",
+            libc::SIGBUS => "SIGBUS",
+            libc::SIGFPE => "SIGFPE",
+            libc::SIGKILL => "SIGKILL",
+            libc::SIGUSR1 => "SIGUSR1",
+            libc::SIGSEGV => "SIGSEGV",
+            libc::SIGUSR2 => "SIGUSR2",
+            libc::SIGPIPE => "SIGPIPE",
+            libc::SIGALRM => "SIGALRM",
+            libc::SIGTERM => "SIGTERM",
+            libc::SIGXCPU => "SIGXCPU",
+            libc::SIGXFSZ => "SIGXFSZ",
+            libc::SIGSYS => "SIGSYS",
+            _ => return None,
+        })
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = signal;
+        None
+    }
+}
+
+fn signal_hint(signal: i32, stack_size: Option<u64>) -> Option<String> {
+    #[cfg(unix)]
+    {
+        Some(match signal {
+            libc::SIGSEGV => format!(
+                "Invalid memory access, or a stack overflow if it recurses deeply{}",
+                stack_limit_note(stack_size),
+            ),
+            libc::SIGABRT => {
+                "Aborted, e.g. by a failed `assert` or an uncaught exception".to_owned()
+            }
+            libc::SIGFPE => "Arithmetic error, e.g. division by zero".to_owned(),
+            libc::SIGBUS => "Invalid memory access".to_owned(),
+            libc::SIGILL => {
+                "Illegal instruction, e.g. reaching `__builtin_unreachable()`".to_owned()
+            }
+            libc::SIGKILL => "Killed, possibly for running out of memory".to_owned(),
+            _ => return None,
+        })
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (signal, stack_size);
+        None
+    }
+}
+
+fn stack_limit_note(stack_size: Option<u64>) -> String {
+    match stack_size {
+        Some(size) => format!(" (stack size limit: {} KiB)", size / 1024),
+        None => "".to_owned(),
+    }
+}
+
+/// Collects `SUMMARY` lines of AddressSanitizer, LeakSanitizer, and UndefinedBehaviorSanitizer, and
+/// `runtime error` lines of UndefinedBehaviorSanitizer.
+fn sanitizer_reports(stderr: &str) -> Vec<String> {
+    let mut reports = vec![];
+
+    for line in stderr.lines() {
+        let line = line.trim();
+
+        let report = if let Some(summary) = line.strip_prefix("SUMMARY: ") {
+            summary
+        } else if line.contains(": runtime error: ") {
+            line
+        } else if line.contains("*** stack smashing detected ***") {
+            "stack smashing detected"
+        } else {
+            continue;
+        };
+
+        if !reports.iter().any(|r| r == report) {
+            reports.push(report.to_owned());
+        }
+    }
+
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sanitizer_reports() {
+        let stderr = r#"a.cpp:5:7: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'
+SUMMARY: UndefinedBehaviorSanitizer: undefined-behavior a.cpp:5:7 in
+=================================================================
+==42==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000014 at pc 0x55d0 bp 0x7ffd sp 0x7ffd
+READ of size 4 at 0x602000000014 thread T0
+    #0 0x55d0 in main a.cpp:9
+SUMMARY: AddressSanitizer: heap-buffer-overflow a.cpp:9 in main
+==42==ABORTING
+"#;
+
+        assert_eq!(
+            vec![
+                "a.cpp:5:7: runtime error: signed integer overflow: 2147483647 + 1 cannot be \
+                 represented in type 'int'"
+                    .to_owned(),
+                "UndefinedBehaviorSanitizer: undefined-behavior a.cpp:5:7 in".to_owned(),
+                "AddressSanitizer: heap-buffer-overflow a.cpp:9 in main".to_owned(),
+            ],
+            super::sanitizer_reports(stderr),
+        );
+    }
+
+    #[test]
+    fn diagnose_stack_overflow() {
+        let notes = super::diagnose(
+            None,
+            "\nthread 'main' has overflowed its stack\nfatal runtime error: stack overflow\n",
+            Some(8 * 1024 * 1024),
+        );
+        assert_eq!(
+            vec!["Likely a stack overflow (stack size limit: 8192 KiB)".to_owned()],
+            notes,
+        );
+    }
+
+    #[test]
+    fn fault_address() {
+        let stderr = "==42==ERROR: AddressSanitizer: SEGV on unknown address 0x7ffd3f8aeff8 (pc \
+                      0x55d0 bp 0x7ffd3f8af010 sp 0x7ffd3f8aeff0 T0)\n";
+
+        let fault = super::fault_address(stderr).unwrap();
+        assert_eq!(
+            super::FaultAddress {
+                addr: 0x7ffd3f8aeff8,
+                sp: Some(0x7ffd3f8aeff0),
+            },
+            fault,
+        );
+        assert!(fault.is_near_stack_pointer());
+        assert!(super::diagnose(None, stderr, None)
+            .iter()
+            .any(|n| n == "Likely a stack overflow"));
+
+        let stderr = "==42==ERROR: AddressSanitizer: SEGV on unknown address 0x000000000000 (pc \
+                      0x55d0 bp 0x7ffd3f8af010 sp 0x7ffd3f8aeff0 T0)\n";
+        assert!(super::diagnose(None, stderr, None)
+            .iter()
+            .any(|n| n == "Likely a null pointer dereference"));
+    }
+}
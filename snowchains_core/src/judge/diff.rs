@@ -0,0 +1,672 @@
+use crate::testsuite::ExpectedOutput;
+use itertools::{EitherOrBoth, Itertools as _};
+use std::{borrow::Cow, cmp, io, iter, ops::Range};
+use termcolor::{Color, WriteColor};
+use unicode_width::UnicodeWidthStr as _;
+
+const CONTEXT: usize = 3;
+const MAX_LCS_TABLE_LEN: usize = 1 << 22;
+const MIN_SIDE_BY_SIDE_COLUMN: usize = 20;
+
+/// Writes the difference between the expected output and the actual one.
+///
+/// Lines and tokens are compared in the way `expected` accepts outputs. For
+/// `ExpectedOutput::UnorderedLines`, the sorted lines are compared and shown with their original line
+/// numbers. For `ExpectedOutput::Tokens`, whitespace is normalized. When `width` is enough for every line to fit in a column, the diff is written side by
+/// side.
+pub(super) fn write_diff(
+    mut wtr: impl WriteColor,
+    expected: &ExpectedOutput,
+    expected_text: &str,
+    actual_text: &str,
+    width: Option<usize>,
+    display_limit: Option<usize>,
+) -> io::Result<()> {
+    let (expected_lines, expected_numbers) = numbered_lines(expected, expected_text);
+    let (actual_lines, actual_numbers) = numbered_lines(expected, actual_text);
+    let expected_lines = expected_lines.iter().map(|l| &**l).collect::<Vec<_>>();
+    let actual_lines = actual_lines.iter().map(|l| &**l).collect::<Vec<_>>();
+
+    let blocks = blocks(&expected_lines, &actual_lines, |l, r| {
+        expected.line_matches(l, r)
+    });
+
+    let first_difference = first_difference(expected, &expected_lines, &actual_lines, &blocks);
+
+    let number_width = cmp::max(expected_lines.len(), actual_lines.len())
+        .to_string()
+        .len();
+
+    let column_width = width
+        .and_then(|w| w.checked_sub(2 * number_width + 5))
+        .map(|w| w / 2)
+        .filter(|&w| w >= MIN_SIDE_BY_SIDE_COLUMN)
+        .filter(|&w| {
+            expected_lines
+                .iter()
+                .chain(&actual_lines)
+                .all(|line| visualize(line).width() <= w)
+        });
+
+    let rows = rows(&blocks, column_width.is_some());
+
+    let note = match expected {
+        ExpectedOutput::UnorderedLines { .. } => ", lines sorted",
+        ExpectedOutput::Tokens { .. } => ", whitespace normalized",
+        _ => "",
+    };
+
+    wtr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
+    if column_width.is_some() {
+        writeln!(wtr, "diff (expected | actual{}):", note)?;
+    } else {
+        writeln!(wtr, "diff (-expected +actual{}):", note)?;
+    }
+    wtr.reset()?;
+
+    let mut written = 0;
+
+    for (i, row) in rows.iter().enumerate() {
+        if matches!(display_limit, Some(l) if l < written) {
+            wtr.set_color(color_spec!(Bold, Fg(Color::Yellow)))?;
+            writeln!(wtr, "... ({} more rows)", rows.len() - i)?;
+            return wtr.reset();
+        }
+
+        let highlight = |side: &Option<(usize, Range<usize>)>, index: usize| match side {
+            Some((line, range)) if *line == index => Some(range.clone()),
+            _ => None,
+        };
+
+        let expected_line = |i: usize| Line {
+            number: expected_numbers[i],
+            text: expected_lines[i],
+            highlight: highlight(&first_difference.expected, i),
+        };
+
+        let actual_line = |j: usize| Line {
+            number: actual_numbers[j],
+            text: actual_lines[j],
+            highlight: highlight(&first_difference.actual, j),
+        };
+
+        match (*row, column_width) {
+            (Row::Omitted(n), _) => {
+                wtr.set_color(color_spec!(Fg(Color::Cyan)))?;
+                writeln!(wtr, "@@ {} lines @@", n)?;
+                wtr.reset()?;
+            }
+            (Row::Same(i, j), None) => {
+                let (n, m) = (expected_numbers[i], actual_numbers[j]);
+                write!(wtr, "  {:>w$} {:>w$} | ", n, m, w = number_width)?;
+                written += write_text(&mut wtr, expected_lines[i], None, None, None)?;
+                writeln!(wtr)?;
+            }
+            (Row::Expected(i), None) => {
+                let line = expected_line(i);
+                wtr.set_color(color_spec!(Fg(Color::Red)))?;
+                write!(wtr, "- {:>w$} {:w$} | ", line.number, "", w = number_width)?;
+                written += line.write(&mut wtr, Color::Red, None)?;
+                writeln!(wtr)?;
+            }
+            (Row::Actual(j), None) => {
+                let line = actual_line(j);
+                wtr.set_color(color_spec!(Fg(Color::Green)))?;
+                write!(wtr, "+ {:w$} {:>w$} | ", "", line.number, w = number_width)?;
+                written += line.write(&mut wtr, Color::Green, None)?;
+                writeln!(wtr)?;
+            }
+            (Row::Both(..), None) => unreachable!("should not be created for unified diffs"),
+            (row, Some(column_width)) => {
+                let (left, right, color) = match row {
+                    Row::Same(i, j) => (Some(i), Some(j), None),
+                    Row::Expected(i) => (Some(i), None, Some(())),
+                    Row::Actual(j) => (None, Some(j), Some(())),
+                    Row::Both(i, j) => (Some(i), Some(j), Some(())),
+                    Row::Omitted(_) => unreachable!(),
+                };
+
+                match left {
+                    Some(i) if color.is_some() => {
+                        let line = expected_line(i);
+                        wtr.set_color(color_spec!(Fg(Color::Red)))?;
+                        write!(wtr, "{:>w$} ", line.number, w = number_width)?;
+                        written += line.write(&mut wtr, Color::Red, Some(column_width))?;
+                    }
+                    Some(i) => {
+                        write!(wtr, "{:>w$} ", expected_numbers[i], w = number_width)?;
+                        written += write_text(
+                            &mut wtr,
+                            expected_lines[i],
+                            None,
+                            None,
+                            Some(column_width),
+                        )?;
+                    }
+                    None => write!(
+                        wtr,
+                        "{:w$} {:c$}",
+                        "",
+                        "",
+                        w = number_width,
+                        c = column_width
+                    )?,
+                }
+
+                write!(wtr, " | ")?;
+
+                match right {
+                    Some(j) if color.is_some() => {
+                        let line = actual_line(j);
+                        wtr.set_color(color_spec!(Fg(Color::Green)))?;
+                        write!(wtr, "{:>w$} ", line.number, w = number_width)?;
+                        written += line.write(&mut wtr, Color::Green, None)?;
+                    }
+                    Some(j) => {
+                        write!(wtr, "{:>w$} ", actual_numbers[j], w = number_width)?;
+                        written += write_text(&mut wtr, actual_lines[j], None, None, None)?;
+                    }
+                    None => {}
+                }
+
+                writeln!(wtr)?;
+            }
+        }
+    }
+
+    return Ok(());
+
+    struct Line<'a> {
+        number: usize,
+        text: &'a str,
+        highlight: Option<Range<usize>>,
+    }
+
+    impl Line<'_> {
+        fn write(
+            &self,
+            wtr: impl WriteColor,
+            color: Color,
+            pad: Option<usize>,
+        ) -> io::Result<usize> {
+            write_text(wtr, self.text, Some(color), self.highlight.clone(), pad)
+        }
+    }
+}
+
+/// Splits `text` into lines with their 1-based line numbers.
+///
+/// For `ExpectedOutput::Tokens`, the whitespace in each line is normalized to single spaces and
+/// blank lines are dropped.
+fn numbered_lines<'a>(expected: &ExpectedOutput, text: &'a str) -> (Vec<Cow<'a, str>>, Vec<usize>) {
+    match expected {
+        ExpectedOutput::UnorderedLines { .. } => text
+            .lines()
+            .enumerate()
+            .sorted_by_key(|&(_, line)| line)
+            .map(|(i, line)| (line.into(), i + 1))
+            .unzip(),
+        ExpectedOutput::Tokens { .. } => text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (line.split_whitespace().join(" "), i + 1))
+            .filter(|(line, _)| !line.is_empty())
+            .map(|(line, n)| (line.into(), n))
+            .unzip(),
+        _ => {
+            let lines = expected
+                .split_lines(text)
+                .into_iter()
+                .map(Into::into)
+                .collect::<Vec<_>>();
+            let numbers = (1..=lines.len()).collect();
+            (lines, numbers)
+        }
+    }
+}
+
+/// The first differing token on each side, with the index of its line.
+#[derive(Debug, Default, PartialEq)]
+struct FirstDifference {
+    expected: Option<(usize, Range<usize>)>,
+    actual: Option<(usize, Range<usize>)>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Block {
+    Same {
+        expected: Range<usize>,
+        actual: Range<usize>,
+    },
+    Changed {
+        expected: Range<usize>,
+        actual: Range<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Row {
+    Same(usize, usize),
+    Expected(usize),
+    Actual(usize),
+    Both(usize, usize),
+    Omitted(usize),
+}
+
+/// Aligns the lines with the longest common subsequence.
+///
+/// If the table would be too large, the lines between the common prefix and suffix are treated as
+/// one changed block.
+fn blocks(expected: &[&str], actual: &[&str], eq: impl Fn(&str, &str) -> bool) -> Vec<Block> {
+    let prefix = expected
+        .iter()
+        .zip(actual)
+        .take_while(|(l, r)| eq(l, r))
+        .count();
+
+    let suffix = expected[prefix..]
+        .iter()
+        .rev()
+        .zip(actual[prefix..].iter().rev())
+        .take_while(|(l, r)| eq(l, r))
+        .count();
+
+    let (n, m) = (
+        expected.len() - prefix - suffix,
+        actual.len() - prefix - suffix,
+    );
+    let (expected_mid, actual_mid) = (&expected[prefix..prefix + n], &actual[prefix..prefix + m]);
+
+    let mut pairs = vec![];
+
+    if (n + 1) * (m + 1) <= MAX_LCS_TABLE_LEN {
+        let at = |i: usize, j: usize| i * (m + 1) + j;
+
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[at(i, j)] = if eq(expected_mid[i], actual_mid[j]) {
+                    lcs[at(i + 1, j + 1)] + 1
+                } else {
+                    cmp::max(lcs[at(i + 1, j)], lcs[at(i, j + 1)])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if eq(expected_mid[i], actual_mid[j]) {
+                pairs.push((prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if lcs[at(i + 1, j)] >= lcs[at(i, j + 1)] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+
+    let anchors = (0..prefix)
+        .map(|k| (k, k))
+        .chain(pairs)
+        .chain((0..suffix).map(|k| (prefix + n + k, prefix + m + k)));
+
+    let mut blocks = vec![];
+    let (mut i, mut j) = (0, 0);
+
+    for (a, b) in anchors {
+        if (i, j) != (a, b) {
+            blocks.push(Block::Changed {
+                expected: i..a,
+                actual: j..b,
+            });
+        }
+        if let Some(Block::Same { expected, actual }) = blocks.last_mut() {
+            expected.end += 1;
+            actual.end += 1;
+        } else {
+            blocks.push(Block::Same {
+                expected: a..a + 1,
+                actual: b..b + 1,
+            });
+        }
+        i = a + 1;
+        j = b + 1;
+    }
+
+    if i < expected.len() || j < actual.len() {
+        blocks.push(Block::Changed {
+            expected: i..expected.len(),
+            actual: j..actual.len(),
+        });
+    }
+
+    blocks
+}
+
+fn rows(blocks: &[Block], side_by_side: bool) -> Vec<Row> {
+    let mut rows = vec![];
+
+    for (k, block) in blocks.iter().enumerate() {
+        match block {
+            Block::Same { expected, actual } => {
+                let same = expected
+                    .clone()
+                    .zip(actual.clone())
+                    .map(|(i, j)| Row::Same(i, j));
+                let len = expected.len();
+
+                let (head, tail) = match (k == 0, k + 1 == blocks.len()) {
+                    (true, true) => (len, 0),
+                    (true, false) => (0, cmp::min(CONTEXT, len)),
+                    (false, true) => (cmp::min(CONTEXT, len), 0),
+                    (false, false) if len <= 2 * CONTEXT => (len, 0),
+                    (false, false) => (CONTEXT, CONTEXT),
+                };
+
+                let omitted = len - head - tail;
+
+                rows.extend(same.clone().take(head));
+                if omitted > 0 {
+                    rows.push(Row::Omitted(omitted));
+                }
+                rows.extend(same.skip(head + omitted));
+            }
+            Block::Changed { expected, actual } if side_by_side => {
+                rows.extend(
+                    expected
+                        .clone()
+                        .zip_longest(actual.clone())
+                        .map(|zip| match zip {
+                            EitherOrBoth::Both(i, j) => Row::Both(i, j),
+                            EitherOrBoth::Left(i) => Row::Expected(i),
+                            EitherOrBoth::Right(j) => Row::Actual(j),
+                        }),
+                );
+            }
+            Block::Changed { expected, actual } => {
+                rows.extend(expected.clone().map(Row::Expected));
+                rows.extend(actual.clone().map(Row::Actual));
+            }
+        }
+    }
+
+    rows
+}
+
+/// Finds the first differing row and the first token that differs in it.
+///
+/// If the row has a line on one side only, its first token is the difference. Nothing is found for
+/// `ExpectedOutput::Regex` since its lines are patterns, not tokens.
+fn first_difference(
+    expected: &ExpectedOutput,
+    expected_lines: &[&str],
+    actual_lines: &[&str],
+    blocks: &[Block],
+) -> FirstDifference {
+    if let ExpectedOutput::Regex { .. } = expected {
+        return FirstDifference::default();
+    }
+
+    let first_token = |line: &str| tokens(line).next().unwrap_or(0..0);
+
+    let (expected_range, actual_range) = match blocks.iter().find_map(|block| match block {
+        Block::Changed { expected, actual } => Some((expected, actual)),
+        Block::Same { .. } => None,
+    }) {
+        Some(ranges) => ranges,
+        None => return FirstDifference::default(),
+    };
+
+    let (i, j) = match (expected_range.is_empty(), actual_range.is_empty()) {
+        (false, false) => (expected_range.start, actual_range.start),
+        (false, true) => {
+            let i = expected_range.start;
+            return FirstDifference {
+                expected: Some((i, first_token(expected_lines[i]))),
+                actual: None,
+            };
+        }
+        (true, _) => {
+            let j = actual_range.start;
+            return FirstDifference {
+                expected: None,
+                actual: Some((j, first_token(actual_lines[j]))),
+            };
+        }
+    };
+
+    let (left, right) = (expected_lines[i], actual_lines[j]);
+
+    tokens(left)
+        .zip_longest(tokens(right))
+        .find_map(|zip| match zip {
+            EitherOrBoth::Both(l, r)
+                if !expected.token_matches(&left[l.clone()], &right[r.clone()]) =>
+            {
+                Some((l, r))
+            }
+            EitherOrBoth::Both(..) => None,
+            EitherOrBoth::Left(l) => Some((l, right.len()..right.len())),
+            EitherOrBoth::Right(r) => Some((left.len()..left.len(), r)),
+        })
+        .map_or_else(FirstDifference::default, |(l, r)| FirstDifference {
+            expected: Some((i, l)),
+            actual: Some((j, r)),
+        })
+}
+
+fn tokens(line: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut rest = 0;
+    iter::from_fn(move || {
+        let start = rest + line[rest..].find(|c: char| !c.is_whitespace())?;
+        let end = line[start..]
+            .find(char::is_whitespace)
+            .map_or(line.len(), |n| start + n);
+        rest = end;
+        Some(start..end)
+    })
+}
+
+/// Returns the number of the bytes of `line`.
+fn write_text(
+    mut wtr: impl WriteColor,
+    line: &str,
+    color: Option<Color>,
+    highlight: Option<Range<usize>>,
+    pad: Option<usize>,
+) -> io::Result<usize> {
+    let segments = match highlight {
+        Some(highlight) => vec![
+            (&line[..highlight.start], false),
+            (&line[highlight.clone()], true),
+            (&line[highlight.end..], false),
+        ],
+        None => vec![(line, false)],
+    };
+
+    let mut width = 0;
+
+    for (segment, highlighted) in segments {
+        let segment = visualize(segment);
+        width += segment.width();
+
+        wtr.reset()?;
+        if highlighted {
+            wtr.set_color(color_spec!(Bold, Underline).set_fg(color))?;
+        } else {
+            wtr.set_color(color_spec!().set_fg(color))?;
+        }
+        wtr.write_all(segment.as_ref())?;
+    }
+    wtr.reset()?;
+
+    if let Some(pad) = pad {
+        write!(wtr, "{}", " ".repeat(pad.saturating_sub(width)))?;
+    }
+
+    Ok(line.len())
+}
+
+fn visualize(s: &str) -> String {
+    s.replace('\r', "\\r").replace('\t', "\\t")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blocks, write_diff, Block, FirstDifference};
+    use crate::testsuite::{ExpectedOutput, LineRegexes};
+    use pretty_assertions::assert_eq;
+    use termcolor::NoColor;
+
+    fn diff(expected: &ExpectedOutput, actual: &str, width: Option<usize>) -> String {
+        let mut wtr = NoColor::new(vec![]);
+        let text = expected.text().unwrap();
+        write_diff(&mut wtr, expected, text, actual, width, None).unwrap();
+        String::from_utf8(wtr.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn unified() {
+        let expected = ExpectedOutput::Exact {
+            text: "1\n2 3\n4\n".into(),
+        };
+
+        assert_eq!(
+            r#"diff (-expected +actual):
+  1 1 | 1
+- 2   | 2 3
++   2 | 2 4
+  3 3 | 4
+  4 4 | 
+"#,
+            diff(&expected, "1\n2 4\n4\n", None),
+        );
+    }
+
+    #[test]
+    fn side_by_side() {
+        let expected = ExpectedOutput::Exact {
+            text: "1\n2 3\n4\n".into(),
+        };
+
+        // Each column is (50 - 2 * 1 - 5) / 2 = 21 wide.
+        let row = |n: usize, l: &str, r: &str| format!("{} {:21} | {} {}\n", n, l, n, r);
+
+        assert_eq!(
+            format!(
+                "diff (expected | actual):\n{}{}{}{}",
+                row(1, "1", "1"),
+                row(2, "2 3", "2 4"),
+                row(3, "4", "4"),
+                row(4, "", ""),
+            ),
+            diff(&expected, "1\n2 4\n4\n", Some(50)),
+        );
+    }
+
+    #[test]
+    fn regex_not_highlighted() {
+        let expected = ExpectedOutput::Regex {
+            text: "[0-9]+\n".into(),
+            patterns: LineRegexes::new("[0-9]+\n"),
+        };
+        let (expected_lines, actual_lines) = (["[0-9]+"], ["x"]);
+        let blocks = blocks(&expected_lines, &actual_lines, |l, r| {
+            expected.line_matches(l, r)
+        });
+
+        assert_eq!(
+            FirstDifference::default(),
+            super::first_difference(&expected, &expected_lines, &actual_lines, &blocks),
+        );
+    }
+
+    #[test]
+    fn first_differing_row() {
+        let expected = ExpectedOutput::Lines {
+            text: "1\n2 3\n4\n".into(),
+        };
+        let (expected_lines, actual_lines) = (["1", "2 3", "4"], ["1", "4", "5 6"]);
+        let blocks = blocks(&expected_lines, &actual_lines, |l, r| l == r);
+
+        // The removed line comes before the pair of the changed lines.
+        assert_eq!(
+            FirstDifference {
+                expected: Some((1, 0..1)),
+                actual: None,
+            },
+            super::first_difference(&expected, &expected_lines, &actual_lines, &blocks),
+        );
+    }
+
+    #[test]
+    fn tokens_whitespace_normalized() {
+        let expected = ExpectedOutput::Tokens {
+            text: "1  2\n\n3 4\n".into(),
+        };
+
+        assert_eq!(
+            r#"diff (-expected +actual, whitespace normalized):
+  1 1 | 1 2
+- 3   | 3 4
++   3 | 3 5
+"#,
+            diff(&expected, " 1 2 \n\t\n3\t5\n", None),
+        );
+    }
+
+    #[test]
+    fn unordered_lines() {
+        let expected = ExpectedOutput::UnorderedLines {
+            text: "b\na\nc\n".into(),
+        };
+
+        assert_eq!(
+            r#"diff (-expected +actual, lines sorted):
+  2 3 | a
+  1 1 | b
+- 3   | c
++   2 | d
+"#,
+            diff(&expected, "b\nd\na\n", None),
+        );
+    }
+
+    #[test]
+    fn blocks_lcs() {
+        let expected = ["1", "2", "3", "4", "5"];
+        let actual = ["1", "3", "4", "x", "5", "6"];
+
+        assert_eq!(
+            vec![
+                Block::Same {
+                    expected: 0..1,
+                    actual: 0..1,
+                },
+                Block::Changed {
+                    expected: 1..2,
+                    actual: 1..1,
+                },
+                Block::Same {
+                    expected: 2..4,
+                    actual: 1..3,
+                },
+                Block::Changed {
+                    expected: 4..4,
+                    actual: 3..4,
+                },
+                Block::Same {
+                    expected: 4..5,
+                    actual: 4..5,
+                },
+                Block::Changed {
+                    expected: 5..5,
+                    actual: 5..6,
+                },
+            ],
+            blocks(&expected, &actual, |l, r| l == r),
+        );
+    }
+}
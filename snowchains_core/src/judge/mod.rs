@@ -0,0 +1,2251 @@
+mod benchmark;
+mod diagnosis;
+mod diff;
+mod report;
+mod sandbox;
+mod shrink;
+
+pub use self::{
+    benchmark::{BenchmarkCase, BenchmarkOutcome, ElapsedStats},
+    shrink::{shrink, ShrinkOutcome},
+};
+
+use self::sandbox::Sandbox;
+
+use crate::testsuite::{BatchTestCase, ExpectedOutput, InteractiveTestCase, TestCase};
+use anyhow::{anyhow, bail, Context as _};
+use futures_util::{select, FutureExt as _};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::{Serialize, Serializer};
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fmt,
+    future::Future,
+    io, iter,
+    mem::ManuallyDrop,
+    num::NonZeroUsize,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    process::{ExitStatus, Stdio},
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tempfile::TempDir;
+use termcolor::{Color, WriteColor};
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _};
+use unicode_width::UnicodeWidthStr as _;
+
+#[derive(Debug, Clone)]
+pub struct JudgeOutcome {
+    pub verdicts: Vec<Verdict>,
+}
+
+impl JudgeOutcome {
+    pub fn print_pretty<W: WriteColor>(
+        &self,
+        mut wtr: W,
+        display_limit: Option<usize>,
+        width: Option<usize>,
+    ) -> io::Result<()> {
+        for (i, verdict) in self.verdicts.iter().enumerate() {
+            if i > 0 {
+                writeln!(wtr)?;
+            }
+
+            write!(
+                wtr,
+                "{}/{} ({:?}) ",
+                i + 1,
+                self.verdicts.len(),
+                verdict.test_case_name().unwrap_or(""),
+            )?;
+
+            wtr.set_color(color_spec!(Bold, Fg(verdict.summary_color())))?;
+            writeln!(wtr, "{}", verdict.summary())?;
+            wtr.reset()?;
+
+            if let Verdict::Skipped { .. } = verdict {
+                continue;
+            }
+
+            let write_text = |wtr: &mut W,
+                              header: &str,
+                              text: &str,
+                              skip_if_empty: bool,
+                              highlight_numbers: bool|
+             -> io::Result<()> {
+                if text.is_empty() && skip_if_empty {
+                    return Ok(());
+                }
+
+                wtr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
+                writeln!(wtr, "{}", header)?;
+                wtr.reset()?;
+
+                if text.is_empty() {
+                    wtr.set_color(color_spec!(Bold, Fg(Color::Yellow)))?;
+                    writeln!(wtr, "EMPTY")?;
+                    return wtr.reset();
+                }
+
+                if matches!(display_limit, Some(l) if l < text.len()) {
+                    wtr.set_color(color_spec!(Bold, Fg(Color::Yellow)))?;
+                    writeln!(wtr, "{} B", text.len())?;
+                    return wtr.reset();
+                }
+
+                for token in parse_to_tokens(text, highlight_numbers) {
+                    match token {
+                        Token::SpcLf(s) | Token::Plain(s) => wtr.write_all(s.as_ref())?,
+                        Token::Cr(n) => {
+                            wtr.set_color(color_spec!(Fg(Color::Yellow)))?;
+                            (0..n).try_for_each(|_| wtr.write_all(b"\\r"))?;
+                            wtr.reset()?;
+                        }
+                        Token::Tab(n) => {
+                            wtr.set_color(color_spec!(Fg(Color::Yellow)))?;
+                            (0..n).try_for_each(|_| wtr.write_all(b"\\t"))?;
+                            wtr.reset()?;
+                        }
+                        Token::OtherWhitespaceControl(s) => {
+                            wtr.set_color(color_spec!(Fg(Color::Yellow)))?;
+                            write!(wtr, "{}", s.escape_unicode())?;
+                            wtr.reset()?;
+                        }
+                        Token::HighlightedNumber(s) => {
+                            wtr.set_color(color_spec!(Fg(Color::Cyan)))?;
+                            wtr.write_all(s.as_ref())?;
+                            wtr.reset()?;
+                        }
+                    }
+                }
+
+                if !text.ends_with('\n') {
+                    wtr.set_color(color_spec!(Fg(Color::Yellow)))?;
+                    writeln!(wtr, "⏎")?;
+                    wtr.reset()?;
+                }
+
+                Ok(())
+            };
+
+            write_text(&mut wtr, "stdin:", verdict.stdin(), false, false)?;
+            match (verdict, verdict.expected().text()) {
+                (
+                    Verdict::WrongAnswer {
+                        stdout, expected, ..
+                    },
+                    Some(text),
+                ) if !matches!(
+                    expected,
+                    ExpectedOutput::Checker { .. } | ExpectedOutput::AnyOf { .. }
+                ) =>
+                {
+                    diff::write_diff(&mut wtr, expected, text, stdout, width, display_limit)?;
+                }
+                (_, expected) => {
+                    let alternatives = verdict.expected().alternatives();
+                    if !alternatives.is_empty() {
+                        for (i, alternative) in alternatives.iter().enumerate() {
+                            if let Some(text) = alternative.text() {
+                                let header =
+                                    format!("expected ({}/{}):", i + 1, alternatives.len());
+                                let is_float = alternative.is_float();
+                                write_text(&mut wtr, &header, text, false, is_float)?;
+                            }
+                        }
+                    } else if let Some(expected) = expected {
+                        let is_float = verdict.expected().is_float();
+                        write_text(&mut wtr, "expected:", expected, false, is_float)?;
+                    }
+                    if let Some(stdout) = verdict.stdout() {
+                        let is_float = verdict.expected().is_float();
+                        write_text(&mut wtr, "actual:", stdout, false, is_float)?;
+                    }
+                }
+            }
+            let (status, stack_size) = match verdict {
+                Verdict::RuntimeError {
+                    status, stack_size, ..
+                } => (Some(*status), *stack_size),
+                _ => (None, None),
+            };
+            let notes = diagnosis::diagnose(status, verdict.stderr().unwrap_or(""), stack_size);
+            if !notes.is_empty() {
+                let notes = notes.iter().map(|s| format!("{}\n", s)).collect::<String>();
+                write_text(&mut wtr, "diagnosis:", &notes, true, false)?;
+            }
+            if let Some(stderr) = verdict.stderr() {
+                let is_float = verdict.expected().is_float();
+                write_text(&mut wtr, "stderr:", stderr, true, is_float)?;
+            }
+            if let Some(judge_message) = verdict.judge_message() {
+                write_text(&mut wtr, "judge:", judge_message, true, false)?;
+            }
+        }
+
+        return wtr.flush();
+
+        #[derive(Debug)]
+        enum Token<'a> {
+            SpcLf(&'a str),
+            Cr(usize),
+            Tab(usize),
+            OtherWhitespaceControl(&'a str),
+            HighlightedNumber(&'a str),
+            Plain(&'a str),
+        }
+
+        fn parse_to_tokens(text: &str, highlight_numbers: bool) -> Vec<Token<'_>> {
+            use nom::branch::alt;
+            use nom::bytes::complete::take_while1;
+            use nom::character::complete::char;
+            use nom::combinator::recognize;
+            use nom::multi::{many0, many1};
+            use nom::number::complete::recognize_float;
+            use nom::IResult;
+
+            let (_, tokens) = many0(alt((
+                spc_lf,
+                cr,
+                tab,
+                other_whitespace_control,
+                highlighted_number_or_plain(highlight_numbers),
+            )))(text)
+            .unwrap();
+
+            return tokens;
+
+            fn spc_lf(input: &str) -> IResult<&str, Token<'_>> {
+                let (rest, target) = take_while1(|c| [' ', '\n'].contains(&c))(input)?;
+                Ok((rest, Token::SpcLf(target)))
+            }
+
+            fn cr(input: &str) -> IResult<&str, Token<'_>> {
+                let (rest, target) = recognize(many1(char('\r')))(input)?;
+                Ok((rest, Token::Cr(target.len())))
+            }
+
+            fn tab(input: &str) -> IResult<&str, Token<'_>> {
+                let (rest, target) = recognize(many1(char('\t')))(input)?;
+                Ok((rest, Token::Tab(target.len())))
+            }
+
+            fn other_whitespace_control(input: &str) -> IResult<&str, Token<'_>> {
+                let (rest, target) =
+                    take_while1(|c: char| c.is_whitespace() || c.is_control())(input)?;
+                Ok((rest, Token::OtherWhitespaceControl(target)))
+            }
+
+            fn highlighted_number_or_plain(
+                highlight_numbers: bool,
+            ) -> fn(&str) -> IResult<&str, Token<'_>> {
+                return if highlight_numbers {
+                    |input| highlighted_number(input).or_else(|_| plain(input))
+                } else {
+                    plain
+                };
+
+                fn highlighted_number(input: &str) -> IResult<&str, Token<'_>> {
+                    let (rest, target) = recognize_float(input)?;
+                    Ok((rest, Token::HighlightedNumber(target)))
+                }
+
+                fn plain(input: &str) -> IResult<&str, Token<'_>> {
+                    let (rest, target) =
+                        take_while1(|c: char| !(c.is_whitespace() || c.is_control()))(input)?;
+                    Ok((rest, Token::Plain(target)))
+                }
+            }
+        }
+    }
+
+    pub fn error_on_fail(&self) -> anyhow::Result<()> {
+        let fails = self
+            .verdicts
+            .iter()
+            .filter(|v| !matches!(v, Verdict::Accepted { .. } | Verdict::Skipped { .. }))
+            .count();
+
+        let skipped = self
+            .verdicts
+            .iter()
+            .filter(|v| matches!(v, Verdict::Skipped { .. }))
+            .count();
+
+        if fails > 0 {
+            bail!(
+                "{}/{} test{} failed{}",
+                fails,
+                self.verdicts.len() - skipped,
+                if fails == 1 { "" } else { "s" },
+                if skipped > 0 {
+                    format!(" ({} skipped)", skipped)
+                } else {
+                    "".to_owned()
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns a serializable view of the outcome in which each text is truncated to `text_limit` bytes.
+    pub fn with_text_limit(&self, text_limit: Option<usize>) -> impl Serialize + '_ {
+        JudgeOutcomeJson {
+            verdicts: self
+                .verdicts
+                .iter()
+                .map(|v| v.to_json_repr(text_limit))
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("should not fail")
+    }
+
+    /// Writes a JUnit XML report. Each text in the failure messages is truncated to `text_limit` bytes.
+    pub fn write_junit(
+        &self,
+        wtr: impl io::Write,
+        name: &str,
+        text_limit: Option<usize>,
+    ) -> io::Result<()> {
+        report::write_junit(wtr, self, name, text_limit)
+    }
+
+    /// Writes a TAP version 13 report. Each text in the diagnostics is truncated to `text_limit` bytes.
+    pub fn write_tap(&self, wtr: impl io::Write, text_limit: Option<usize>) -> io::Result<()> {
+        report::write_tap(wtr, self, text_limit)
+    }
+}
+
+impl Serialize for JudgeOutcome {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.with_text_limit(None).serialize(serializer)
+    }
+}
+
+#[derive(Serialize)]
+struct JudgeOutcomeJson<'a> {
+    verdicts: Vec<VerdictJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct VerdictJson<'a> {
+    test_case_name: Option<&'a str>,
+    verdict: &'static str,
+    elapsed_ms: Option<u64>,
+    cpu_time_ms: Option<u64>,
+    memory: Option<u64>,
+    timelimit_ms: Option<u64>,
+    memorylimit: Option<u64>,
+    outputlimit: Option<u64>,
+    exit_status: Option<ExitStatusJson>,
+    stdin: TextJson<'a>,
+    stdout: Option<TextJson<'a>>,
+    stderr: Option<TextJson<'a>>,
+    expected: Option<TextJson<'a>>,
+    judge_message: Option<TextJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct ExitStatusJson {
+    success: bool,
+    code: Option<i32>,
+    signal: Option<i32>,
+}
+
+impl From<ExitStatus> for ExitStatusJson {
+    fn from(status: ExitStatus) -> Self {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+
+        Self {
+            success: status.success(),
+            code: status.code(),
+            signal,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TextJson<'a> {
+    text: &'a str,
+    truncated: bool,
+}
+
+impl<'a> TextJson<'a> {
+    fn new(text: &'a str, limit: Option<usize>) -> Self {
+        let (text, truncated) = truncate(text, limit);
+        Self { text, truncated }
+    }
+}
+
+/// Truncates `text` to at most `limit` bytes on a char boundary.
+fn truncate(text: &str, limit: Option<usize>) -> (&str, bool) {
+    match limit {
+        Some(limit) if limit < text.len() => {
+            let end = (0..=limit)
+                .rev()
+                .find(|&i| text.is_char_boundary(i))
+                .unwrap_or(0);
+            (&text[..end], true)
+        }
+        _ => (text, false),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Verdict {
+    Accepted {
+        test_case_name: Option<String>,
+        elapsed: Duration,
+        cpu_time: Option<Duration>,
+        stdin: Arc<str>,
+        stdout: Arc<str>,
+        stderr: Arc<str>,
+        expected: ExpectedOutput,
+        status: ExitStatus,
+        memory: Option<u64>,
+        judge_message: Option<Arc<str>>,
+    },
+    WrongAnswer {
+        test_case_name: Option<String>,
+        elapsed: Duration,
+        cpu_time: Option<Duration>,
+        stdin: Arc<str>,
+        stdout: Arc<str>,
+        stderr: Arc<str>,
+        expected: ExpectedOutput,
+        status: ExitStatus,
+        memory: Option<u64>,
+        judge_message: Option<Arc<str>>,
+    },
+    RuntimeError {
+        test_case_name: Option<String>,
+        elapsed: Duration,
+        cpu_time: Option<Duration>,
+        stdin: Arc<str>,
+        stdout: Arc<str>,
+        stderr: Arc<str>,
+        expected: ExpectedOutput,
+        status: ExitStatus,
+        memory: Option<u64>,
+        judge_message: Option<Arc<str>>,
+        /// Stack size limit the process ran with, in bytes.
+        stack_size: Option<u64>,
+    },
+    TimelimitExceeded {
+        test_case_name: Option<String>,
+        timelimit: Duration,
+        cpu_time: Option<Duration>,
+        stdin: Arc<str>,
+        expected: ExpectedOutput,
+        memory: Option<u64>,
+    },
+    MemoryLimitExceeded {
+        test_case_name: Option<String>,
+        elapsed: Duration,
+        cpu_time: Option<Duration>,
+        stdin: Arc<str>,
+        stdout: Arc<str>,
+        stderr: Arc<str>,
+        expected: ExpectedOutput,
+        memory: u64,
+        memorylimit: u64,
+    },
+    OutputLimitExceeded {
+        test_case_name: Option<String>,
+        elapsed: Duration,
+        cpu_time: Option<Duration>,
+        stdin: Arc<str>,
+        stdout: Arc<str>,
+        stderr: Arc<str>,
+        expected: ExpectedOutput,
+        memory: Option<u64>,
+        outputlimit: u64,
+    },
+    /// Killed for exceeding the file size limit of the sandbox.
+    SandboxViolation {
+        test_case_name: Option<String>,
+        elapsed: Duration,
+        cpu_time: Option<Duration>,
+        stdin: Arc<str>,
+        stdout: Arc<str>,
+        stderr: Arc<str>,
+        expected: ExpectedOutput,
+        status: ExitStatus,
+        memory: Option<u64>,
+        reason: &'static str,
+    },
+    Skipped {
+        test_case_name: Option<String>,
+        stdin: Arc<str>,
+        expected: ExpectedOutput,
+    },
+}
+
+impl Serialize for Verdict {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.to_json_repr(None).serialize(serializer)
+    }
+}
+
+impl Verdict {
+    fn skipped(test_case: &TestCase) -> Self {
+        let (stdin, expected) = match test_case {
+            TestCase::Batch(test_case) => (test_case.input.clone(), test_case.output.clone()),
+            TestCase::Interactive(test_case) => (test_case.input.clone(), ExpectedOutput::Pass),
+        };
+
+        Self::Skipped {
+            test_case_name: test_case.name().map(ToOwned::to_owned),
+            stdin,
+            expected,
+        }
+    }
+
+    pub fn test_case_name(&self) -> Option<&str> {
+        match self {
+            Verdict::Accepted { test_case_name, .. }
+            | Verdict::WrongAnswer { test_case_name, .. }
+            | Verdict::RuntimeError { test_case_name, .. }
+            | Verdict::TimelimitExceeded { test_case_name, .. }
+            | Verdict::MemoryLimitExceeded { test_case_name, .. }
+            | Verdict::OutputLimitExceeded { test_case_name, .. }
+            | Verdict::SandboxViolation { test_case_name, .. }
+            | Verdict::Skipped { test_case_name, .. } => test_case_name.as_deref(),
+        }
+    }
+
+    fn stdin(&self) -> &str {
+        match self {
+            Verdict::Accepted { stdin, .. }
+            | Verdict::WrongAnswer { stdin, .. }
+            | Verdict::RuntimeError { stdin, .. }
+            | Verdict::TimelimitExceeded { stdin, .. }
+            | Verdict::MemoryLimitExceeded { stdin, .. }
+            | Verdict::OutputLimitExceeded { stdin, .. }
+            | Verdict::SandboxViolation { stdin, .. }
+            | Verdict::Skipped { stdin, .. } => stdin,
+        }
+    }
+
+    fn stdout(&self) -> Option<&str> {
+        match self {
+            Verdict::Accepted { stdout, .. }
+            | Verdict::WrongAnswer { stdout, .. }
+            | Verdict::RuntimeError { stdout, .. }
+            | Verdict::MemoryLimitExceeded { stdout, .. }
+            | Verdict::OutputLimitExceeded { stdout, .. }
+            | Verdict::SandboxViolation { stdout, .. } => Some(stdout),
+            Verdict::TimelimitExceeded { .. } | Verdict::Skipped { .. } => None,
+        }
+    }
+
+    fn stderr(&self) -> Option<&str> {
+        match self {
+            Verdict::Accepted { stderr, .. }
+            | Verdict::WrongAnswer { stderr, .. }
+            | Verdict::RuntimeError { stderr, .. }
+            | Verdict::MemoryLimitExceeded { stderr, .. }
+            | Verdict::OutputLimitExceeded { stderr, .. }
+            | Verdict::SandboxViolation { stderr, .. } => Some(stderr),
+            Verdict::TimelimitExceeded { .. } | Verdict::Skipped { .. } => None,
+        }
+    }
+
+    fn judge_message(&self) -> Option<&str> {
+        match self {
+            Verdict::Accepted { judge_message, .. }
+            | Verdict::WrongAnswer { judge_message, .. }
+            | Verdict::RuntimeError { judge_message, .. } => judge_message.as_deref(),
+            Verdict::TimelimitExceeded { .. }
+            | Verdict::MemoryLimitExceeded { .. }
+            | Verdict::OutputLimitExceeded { .. }
+            | Verdict::SandboxViolation { .. }
+            | Verdict::Skipped { .. } => None,
+        }
+    }
+
+    fn cpu_time(&self) -> Option<Duration> {
+        match *self {
+            Verdict::Accepted { cpu_time, .. }
+            | Verdict::WrongAnswer { cpu_time, .. }
+            | Verdict::RuntimeError { cpu_time, .. }
+            | Verdict::TimelimitExceeded { cpu_time, .. }
+            | Verdict::MemoryLimitExceeded { cpu_time, .. }
+            | Verdict::OutputLimitExceeded { cpu_time, .. }
+            | Verdict::SandboxViolation { cpu_time, .. } => cpu_time,
+            Verdict::Skipped { .. } => None,
+        }
+    }
+
+    fn memory(&self) -> Option<u64> {
+        match *self {
+            Verdict::Accepted { memory, .. }
+            | Verdict::WrongAnswer { memory, .. }
+            | Verdict::RuntimeError { memory, .. }
+            | Verdict::TimelimitExceeded { memory, .. }
+            | Verdict::OutputLimitExceeded { memory, .. }
+            | Verdict::SandboxViolation { memory, .. } => memory,
+            Verdict::Skipped { .. } => None,
+            Verdict::MemoryLimitExceeded { memory, .. } => Some(memory),
+        }
+    }
+
+    fn expected(&self) -> &ExpectedOutput {
+        match self {
+            Verdict::Accepted { expected, .. }
+            | Verdict::WrongAnswer { expected, .. }
+            | Verdict::RuntimeError { expected, .. }
+            | Verdict::TimelimitExceeded { expected, .. }
+            | Verdict::MemoryLimitExceeded { expected, .. }
+            | Verdict::OutputLimitExceeded { expected, .. }
+            | Verdict::SandboxViolation { expected, .. }
+            | Verdict::Skipped { expected, .. } => expected,
+        }
+    }
+
+    fn to_json_repr(&self, text_limit: Option<usize>) -> VerdictJson<'_> {
+        let millis = |d: Duration| d.as_millis() as u64;
+        let text = |s| TextJson::new(s, text_limit);
+
+        let (timelimit, memorylimit, outputlimit, exit_status) = match *self {
+            Self::Accepted { status, .. }
+            | Self::WrongAnswer { status, .. }
+            | Self::RuntimeError { status, .. }
+            | Self::SandboxViolation { status, .. } => (None, None, None, Some(status.into())),
+            Self::TimelimitExceeded { timelimit, .. } => (Some(timelimit), None, None, None),
+            Self::MemoryLimitExceeded { memorylimit, .. } => (None, Some(memorylimit), None, None),
+            Self::OutputLimitExceeded { outputlimit, .. } => (None, None, Some(outputlimit), None),
+            Self::Skipped { .. } => (None, None, None, None),
+        };
+
+        VerdictJson {
+            test_case_name: self.test_case_name(),
+            verdict: self.kind(),
+            elapsed_ms: self.elapsed().map(millis),
+            cpu_time_ms: self.cpu_time().map(millis),
+            memory: self.memory(),
+            timelimit_ms: timelimit.map(millis),
+            memorylimit,
+            outputlimit,
+            exit_status,
+            stdin: text(self.stdin()),
+            stdout: self.stdout().map(text),
+            stderr: self.stderr().map(text),
+            expected: self.expected().text().map(|s| text(s)),
+            judge_message: self.judge_message().map(text),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Accepted { .. } => "Accepted",
+            Self::WrongAnswer { .. } => "WrongAnswer",
+            Self::RuntimeError { .. } => "RuntimeError",
+            Self::TimelimitExceeded { .. } => "TimelimitExceeded",
+            Self::MemoryLimitExceeded { .. } => "MemoryLimitExceeded",
+            Self::OutputLimitExceeded { .. } => "OutputLimitExceeded",
+            Self::SandboxViolation { .. } => "SandboxViolation",
+            Self::Skipped { .. } => "Skipped",
+        }
+    }
+
+    fn elapsed(&self) -> Option<Duration> {
+        match *self {
+            Verdict::Accepted { elapsed, .. }
+            | Verdict::WrongAnswer { elapsed, .. }
+            | Verdict::RuntimeError { elapsed, .. }
+            | Verdict::MemoryLimitExceeded { elapsed, .. }
+            | Verdict::OutputLimitExceeded { elapsed, .. }
+            | Verdict::SandboxViolation { elapsed, .. } => Some(elapsed),
+            Verdict::TimelimitExceeded { .. } | Verdict::Skipped { .. } => None,
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut usage = "".to_owned();
+        if let Some(cpu_time) = self.cpu_time() {
+            usage += &format!(", CPU: {} ms", cpu_time.as_millis());
+        }
+        if let Some(memory) = self.memory() {
+            usage += &format!(", {} KiB", memory / 1024);
+        }
+
+        match self {
+            Self::Accepted { elapsed, .. } => {
+                format!("Accepted ({} ms{})", elapsed.as_millis(), usage)
+            }
+            Self::TimelimitExceeded { timelimit, .. } => {
+                format!("Timelimit Exceeded ({} ms{})", timelimit.as_millis(), usage)
+            }
+            Self::MemoryLimitExceeded {
+                elapsed,
+                memorylimit,
+                ..
+            } => format!(
+                "Memory Limit Exceeded ({} ms{}, limit: {} KiB)",
+                elapsed.as_millis(),
+                usage,
+                memorylimit / 1024,
+            ),
+            Self::OutputLimitExceeded {
+                elapsed,
+                outputlimit,
+                ..
+            } => format!(
+                "Output Limit Exceeded ({} ms{}, limit: {} B)",
+                elapsed.as_millis(),
+                usage,
+                outputlimit,
+            ),
+            Self::SandboxViolation {
+                elapsed, reason, ..
+            } => format!(
+                "Sandbox Violation ({} ms{}, {})",
+                elapsed.as_millis(),
+                usage,
+                reason,
+            ),
+            Self::Skipped { .. } => "Skipped".to_owned(),
+            Self::WrongAnswer { elapsed, .. } => {
+                format!("Wrong Answer ({} ms{})", elapsed.as_millis(), usage)
+            }
+            Self::RuntimeError {
+                elapsed, status, ..
+            } => format!(
+                "Runtime Error ({} ms{}, {})",
+                elapsed.as_millis(),
+                usage,
+                diagnosis::describe_status(*status),
+            ),
+        }
+    }
+
+    fn summary_color(&self) -> Color {
+        match self {
+            Self::Accepted { .. } => Color::Green,
+            Self::TimelimitExceeded { .. }
+            | Self::MemoryLimitExceeded { .. }
+            | Self::OutputLimitExceeded { .. }
+            | Self::SandboxViolation { .. } => Color::Red,
+            Self::Skipped { .. } => Color::White,
+            Self::WrongAnswer { .. } | Self::RuntimeError { .. } => Color::Yellow,
+        }
+    }
+
+    fn summary_style(&self) -> &'static str {
+        match self {
+            Self::Accepted { .. } => ".bold.green",
+            Self::TimelimitExceeded { .. }
+            | Self::MemoryLimitExceeded { .. }
+            | Self::OutputLimitExceeded { .. }
+            | Self::SandboxViolation { .. } => ".bold.red",
+            Self::Skipped { .. } => ".bold.white",
+            Self::WrongAnswer { .. } | Self::RuntimeError { .. } => ".bold.yellow",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandExpression {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+    pub cwd: PathBuf,
+    pub env: BTreeMap<OsString, OsString>,
+    pub resource_limits: ResourceLimits,
+}
+
+/// Resource limits of a command. Each of them is applied with `setrlimit` (Unix only).
+///
+/// Only the soft limits are set, and each of them is capped at the hard limit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// In bytes.
+    pub stack_size: Option<u64>,
+    /// In bytes.
+    pub address_space: Option<u64>,
+    pub open_files: Option<u64>,
+    /// In seconds.
+    pub cpu_time: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Names the limits in an error of spawning a process with them. An error in `setrlimit` only
+    /// reaches us as an `errno`.
+    fn context(self, err: io::Error) -> anyhow::Error {
+        let Self {
+            stack_size,
+            address_space,
+            open_files,
+            cpu_time,
+        } = self;
+
+        let limits = [
+            stack_size.map(|n| format!("stack size = {} B", n)),
+            address_space.map(|n| format!("address space = {} B", n)),
+            open_files.map(|n| format!("open files = {}", n)),
+            cpu_time.map(|n| format!("CPU time = {} s", n)),
+        ]
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+
+        if limits.is_empty() {
+            err.into()
+        } else {
+            anyhow::Error::new(err).context(format!(
+                "Could not spawn the process with the resource limits ({})",
+                limits.join(", "),
+            ))
+        }
+    }
+}
+
+impl CommandExpression {
+    /// Moves the working directory to `dir`. The program and the arguments that name existing
+    /// paths relative to the original one are made absolute.
+    fn in_dir(&self, dir: &Path) -> Self {
+        let absolute = |arg: &OsString| {
+            let path = Path::new(arg);
+            if path.is_relative() && self.cwd.join(path).exists() {
+                self.cwd.join(path).into()
+            } else {
+                arg.clone()
+            }
+        };
+
+        Self {
+            program: absolute(&self.program),
+            args: self.args.iter().map(absolute).collect(),
+            cwd: dir.to_owned(),
+            env: self.env.clone(),
+            resource_limits: self.resource_limits,
+        }
+    }
+
+    fn build(&self) -> tokio::process::Command {
+        let mut cmd = tokio::process::Command::new(&self.program);
+
+        cmd.args(&self.args)
+            .current_dir(&self.cwd)
+            .envs(&self.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        // Put the process in a new process group so that we can kill its descendants together.
+        #[cfg(unix)]
+        unsafe {
+            let ResourceLimits {
+                stack_size,
+                address_space,
+                open_files,
+                cpu_time,
+            } = self.resource_limits;
+
+            cmd.pre_exec(move || {
+                if libc::setpgid(0, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+
+                for &(resource, limit) in &[
+                    (libc::RLIMIT_STACK, stack_size),
+                    (libc::RLIMIT_AS, address_space),
+                    (libc::RLIMIT_NOFILE, open_files),
+                    (libc::RLIMIT_CPU, cpu_time),
+                ] {
+                    if let Some(limit) = limit {
+                        let mut current = libc::rlimit {
+                            rlim_cur: 0,
+                            rlim_max: 0,
+                        };
+                        if libc::getrlimit(resource, &mut current) == -1 {
+                            return Err(io::Error::last_os_error());
+                        }
+                        current.rlim_cur = (limit as libc::rlim_t).min(current.rlim_max);
+                        if libc::setrlimit(resource, &current) == -1 {
+                            return Err(io::Error::last_os_error());
+                        }
+                    }
+                }
+
+                Ok(())
+            });
+        }
+
+        cmd
+    }
+}
+
+impl Serialize for CommandExpression {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Repr {
+            program: String,
+            args: Vec<String>,
+            cwd: String,
+            env: BTreeMap<String, String>,
+        }
+
+        let lossy = |s: &OsString| s.to_string_lossy().into_owned();
+
+        Repr {
+            program: lossy(&self.program),
+            args: self.args.iter().map(lossy).collect(),
+            cwd: self.cwd.to_string_lossy().into_owned(),
+            env: self.env.iter().map(|(k, v)| (lossy(k), lossy(v))).collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A child process.
+///
+/// On Unix, `wait_with_usage` reaps it with `wait4` instead of `tokio`. After that, its pid may be
+/// recycled, so neither we nor `tokio` signal or wait for it any more.
+struct Child {
+    inner: ManuallyDrop<tokio::process::Child>,
+    reaper: Arc<Mutex<Reaper>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Reaper {
+    Tokio,
+    /// A thread in `wait_with_usage` is waiting for the child.
+    WaitWithUsage,
+    Reaped,
+}
+
+impl Child {
+    fn spawn(cmd: &mut tokio::process::Command) -> io::Result<Self> {
+        Ok(Self {
+            inner: ManuallyDrop::new(cmd.spawn()?),
+            reaper: Arc::new(Mutex::new(Reaper::Tokio)),
+        })
+    }
+}
+
+impl Deref for Child {
+    type Target = tokio::process::Child;
+
+    fn deref(&self) -> &tokio::process::Child {
+        &self.inner
+    }
+}
+
+impl DerefMut for Child {
+    fn deref_mut(&mut self) -> &mut tokio::process::Child {
+        &mut self.inner
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        match *self.reaper.lock().unwrap() {
+            Reaper::Tokio => unsafe { ManuallyDrop::drop(&mut self.inner) },
+            // The waiting thread reaps it.
+            Reaper::WaitWithUsage => {
+                #[cfg(unix)]
+                unsafe {
+                    libc::kill(self.inner.id() as _, libc::SIGKILL);
+                }
+            }
+            Reaper::Reaped => {}
+        }
+    }
+}
+
+/// Kills the process and every process in its process group.
+fn kill_tree(child: &mut Child) {
+    let reaper = child.reaper.lock().unwrap();
+    if *reaper != Reaper::Reaped {
+        kill_process_group(child.inner.id());
+        let _ = child.inner.kill();
+    }
+}
+
+#[cfg(unix)]
+fn kill_process_group(pgid: u32) {
+    unsafe {
+        libc::kill(-(pgid as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_: u32) {}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JudgeOptions {
+    /// Decides `TimelimitExceeded` on user+sys CPU time instead of wall-clock time.
+    pub timelimit_on_cpu_time: bool,
+    /// Overrides `outputlimit` of the test cases. In bytes. It applies to stdout and stderr
+    /// separately, and either of them exceeding it is `OutputLimitExceeded`.
+    pub output_limit: Option<u64>,
+    /// Number of test cases run at the same time. Defaults to the number of CPUs.
+    pub jobs: Option<NonZeroUsize>,
+    /// Cancels the rest of the test cases after the first failure.
+    pub fail_fast: bool,
+    /// Runs the solutions in a sandbox. Supported only on Linux.
+    pub sandbox: Option<SandboxOptions>,
+    /// Shows one progress bar with the counts of the verdicts instead of one spinner for each test
+    /// case when there are more test cases than this. Only the failures are listed.
+    pub compact_progress_above: Option<usize>,
+}
+
+/// Resource limits in the sandbox. Each of them is applied with `setrlimit`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SandboxOptions {
+    /// `RLIMIT_AS`. In bytes.
+    pub address_space: Option<u64>,
+    /// `RLIMIT_NPROC`.
+    pub processes: Option<u64>,
+    /// `RLIMIT_FSIZE`. In bytes.
+    pub file_size: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+enum Interruption {
+    CtrlC(String),
+    FailFast,
+}
+
+impl fmt::Display for Interruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CtrlC(err_msg) => f.write_str(err_msg),
+            Self::FailFast => f.write_str("Cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for Interruption {}
+
+impl JudgeOptions {
+    /// When to kill the process.
+    fn timeout(self, timelimit: Duration) -> Duration {
+        if self.timelimit_on_cpu_time {
+            // A process may sleep or be starved. Give it more wall-clock time.
+            2 * timelimit + Duration::from_secs(1)
+        } else {
+            timelimit + Duration::from_millis(100)
+        }
+    }
+
+    fn exceeds(self, timelimit: Duration, elapsed: Duration, cpu_time: Option<Duration>) -> bool {
+        match cpu_time {
+            Some(cpu_time) if self.timelimit_on_cpu_time => timelimit < cpu_time,
+            _ => timelimit < elapsed,
+        }
+    }
+}
+
+pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
+    draw_target: ProgressDrawTarget,
+    ctrl_c: fn() -> C,
+    cmd: &CommandExpression,
+    test_cases: &[TestCase],
+    options: JudgeOptions,
+) -> anyhow::Result<JudgeOutcome> {
+    let num_test_cases = test_cases.len();
+
+    let quoted_name_width = test_cases
+        .iter()
+        .flat_map(TestCase::name)
+        .map(|s| format!("{:?}", s).width())
+        .max()
+        .unwrap_or(0);
+
+    let sandbox = options.sandbox.map(Sandbox::new).transpose()?.map(Arc::new);
+
+    let mp = MultiProgress::with_draw_target(draw_target);
+
+    let prefix = |i: usize, test_case: &TestCase| {
+        format!(
+            "{}/{} ({} ",
+            align_right(&(i + 1).to_string(), num_test_cases.to_string().len()),
+            num_test_cases,
+            align_left(
+                &format!("{:?})", test_case.name().unwrap_or("")),
+                quoted_name_width + 1,
+            ),
+        )
+    };
+
+    let aggregate = match options.compact_progress_above {
+        Some(threshold) if num_test_cases > threshold => {
+            let pb = mp.add(ProgressBar::new(num_test_cases as _));
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("{bar:40} {pos}/{len} {msg:bold}")
+                    .progress_chars("=> "),
+            );
+            pb.set_message("Judging...");
+            Some((pb, Arc::new(Mutex::new(BTreeMap::new()))))
+        }
+        _ => None,
+    };
+
+    let resource_limits = cmd.resource_limits;
+
+    let mut targets = vec![];
+
+    for (i, test_case) in test_cases.iter().enumerate() {
+        let progress = if let Some((pb, counts)) = &aggregate {
+            Progress::Aggregate {
+                pb: pb.clone(),
+                prefix: prefix(i, test_case),
+                counts: counts.clone(),
+            }
+        } else {
+            let pb = mp.add(ProgressBar::new_spinner());
+            pb.set_style(progress_style("{prefix}{spinner} {msg:bold}"));
+            pb.set_prefix(&prefix(i, test_case));
+            pb.set_message("Judging...");
+            pb.enable_steady_tick(50);
+            Progress::Each(pb)
+        };
+
+        targets.push((test_case.clone(), progress));
+    }
+
+    let cmd = Arc::new(cmd.clone());
+
+    let mut rt = tokio::runtime::Builder::new()
+        .enable_io()
+        .enable_time()
+        .basic_scheduler()
+        .threaded_scheduler()
+        .build()?;
+
+    let outcome = rt.spawn(async move {
+        let num_targets = targets.len();
+
+        // At most one for Ctrl-C and one for `fail_fast`.
+        let (ctrl_c_tx, ctrl_c_rx) = tokio::sync::broadcast::channel(2);
+        let fail_fast_tx = ctrl_c_tx.clone();
+        let fail_fast_sent = Arc::new(AtomicBool::new(false));
+
+        let mut ctrl_c_rxs = iter::once(ctrl_c_rx)
+            .chain(iter::repeat_with(|| ctrl_c_tx.subscribe()))
+            .take(num_targets)
+            .collect::<Vec<_>>();
+
+        tokio::task::spawn(async move {
+            let err_msg = match ctrl_c().await {
+                Ok(()) => "Recieved Ctrl-C".to_owned(),
+                Err(err) => err.to_string(),
+            };
+            ctrl_c_tx.send(Interruption::CtrlC(err_msg)).unwrap();
+        });
+
+        let jobs = options.jobs.map_or_else(num_cpus::get, NonZeroUsize::get);
+
+        let (mut job_start_tx, mut job_start_rx) = tokio::sync::mpsc::channel(jobs);
+        for _ in 0..jobs {
+            job_start_tx.send(()).await?;
+        }
+
+        let mut results = vec![];
+
+        for (i, (test_case, progress)) in targets.into_iter().enumerate() {
+            job_start_rx.recv().await;
+
+            let cmd = cmd.clone();
+            let sandbox = sandbox.clone();
+
+            let mut job_start_tx = job_start_tx.clone();
+            let mut ctrl_c_rx = ctrl_c_rxs.pop().expect("should have enough length");
+            let fail_fast_tx = fail_fast_tx.clone();
+            let fail_fast_sent = fail_fast_sent.clone();
+
+            results.push(tokio::task::spawn(async move {
+                let skipped = Verdict::skipped(&test_case);
+
+                let result = match ctrl_c_rx.try_recv() {
+                    Ok(Interruption::CtrlC(err_msg)) => bail!("{}", err_msg),
+                    Ok(Interruption::FailFast) => Ok(skipped.clone()),
+                    Err(_) => {
+                        // Keep the scratch directory until the process exits.
+                        let (cmd, scratch) = tokio::task::block_in_place(|| {
+                            prepare(&cmd, &test_case, sandbox.as_deref())
+                        })?;
+
+                        match test_case {
+                            TestCase::Batch(test_case) => {
+                                judge_batch(
+                                    cmd,
+                                    scratch.as_ref().map(TempDir::path),
+                                    test_case,
+                                    resource_limits,
+                                    options,
+                                    &mut ctrl_c_rx,
+                                )
+                                .await
+                            }
+                            TestCase::Interactive(test_case) => {
+                                judge_interactive(
+                                    cmd,
+                                    test_case,
+                                    resource_limits,
+                                    options,
+                                    &mut ctrl_c_rx,
+                                )
+                                .await
+                            }
+                        }
+                    }
+                };
+
+                let verdict = match result {
+                    Err(err) if matches!(err.downcast_ref(), Some(Interruption::FailFast)) => {
+                        skipped
+                    }
+                    result => result?,
+                };
+
+                if options.fail_fast
+                    && !matches!(verdict, Verdict::Accepted { .. } | Verdict::Skipped { .. })
+                    && !fail_fast_sent.swap(true, atomic::Ordering::SeqCst)
+                {
+                    let _ = fail_fast_tx.send(Interruption::FailFast);
+                }
+
+                tokio::task::block_in_place(|| progress.finish(&verdict));
+
+                job_start_tx.send(()).await?;
+
+                Ok::<_, anyhow::Error>((i, verdict))
+            }));
+        }
+
+        let mut verdicts = vec![None; num_targets];
+        for result in results {
+            let (i, element) = result.await??;
+            verdicts[i] = Some(element);
+        }
+        let verdicts = verdicts.into_iter().map(Option::unwrap).collect();
+
+        Ok::<_, anyhow::Error>(JudgeOutcome { verdicts })
+    });
+
+    mp.join()?;
+
+    return rt.block_on(outcome)?;
+
+    enum Progress {
+        /// A spinner for the test case.
+        Each(ProgressBar),
+        /// The progress bar shared by all of the test cases.
+        Aggregate {
+            pb: ProgressBar,
+            prefix: String,
+            counts: Arc<Mutex<BTreeMap<&'static str, usize>>>,
+        },
+    }
+
+    impl Progress {
+        fn finish(&self, verdict: &Verdict) {
+            match self {
+                Self::Each(pb) => {
+                    pb.set_style(progress_style(&format!(
+                        "{{prefix}}{{msg:{}}}",
+                        verdict.summary_style(),
+                    )));
+                    pb.finish_with_message(&verdict.summary());
+                }
+                Self::Aggregate { pb, prefix, counts } => {
+                    if !matches!(verdict, Verdict::Accepted { .. } | Verdict::Skipped { .. }) {
+                        pb.println(format!("{}{}", prefix, verdict.summary()));
+                    }
+
+                    let mut counts = counts.lock().unwrap();
+                    *counts.entry(verdict.kind()).or_default() += 1;
+                    let msg = counts
+                        .iter()
+                        .map(|(kind, n)| format!("{}: {}", kind, n))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    pb.inc(1);
+                    if pb.position() == pb.length() {
+                        pb.finish_with_message(&msg);
+                    } else {
+                        pb.set_message(&msg);
+                    }
+                }
+            }
+        }
+    }
+
+    fn progress_style(template: impl AsRef<str>) -> ProgressStyle {
+        ProgressStyle::default_spinner().template(template.as_ref())
+    }
+
+    fn align_left(s: &str, n: usize) -> String {
+        let spaces = n.saturating_sub(s.width());
+        s.chars().chain(itertools::repeat_n(' ', spaces)).collect()
+    }
+
+    fn align_right(s: &str, n: usize) -> String {
+        let spaces = n.saturating_sub(s.width());
+        itertools::repeat_n(' ', spaces).chain(s.chars()).collect()
+    }
+}
+
+/// Builds the command for `test_case`, with a scratch directory if it uses files or the sandbox.
+fn prepare(
+    cmd: &CommandExpression,
+    test_case: &TestCase,
+    sandbox: Option<&Sandbox>,
+) -> anyhow::Result<(tokio::process::Command, Option<TempDir>)> {
+    let file_io = matches!(
+        test_case,
+        TestCase::Batch(BatchTestCase { inputfile, outputfile, .. })
+            if inputfile.is_some() || outputfile.is_some()
+    );
+
+    let scratch = if file_io || sandbox.is_some() {
+        Some(
+            tempfile::Builder::new()
+                .prefix("snowchains-judge")
+                .tempdir()?,
+        )
+    } else {
+        None
+    };
+
+    let mut cmd = match &scratch {
+        Some(scratch) if file_io => cmd.in_dir(scratch.path()).build(),
+        _ => cmd.build(),
+    };
+
+    if let (Some(sandbox), Some(scratch)) = (sandbox, &scratch) {
+        sandbox.apply(&mut cmd, scratch.path())?;
+    }
+
+    Ok((cmd, scratch))
+}
+
+async fn judge_batch(
+    mut cmd: tokio::process::Command,
+    scratch: Option<&Path>,
+    test_case: BatchTestCase,
+    resource_limits: ResourceLimits,
+    options: JudgeOptions,
+    ctrl_c_rx: &mut tokio::sync::broadcast::Receiver<Interruption>,
+) -> anyhow::Result<Verdict> {
+    let BatchTestCase {
+        name: test_case_name,
+        timelimit,
+        memorylimit,
+        outputlimit,
+        inputfile,
+        outputfile,
+        input: stdin,
+        output: expected,
+    } = test_case;
+
+    let outputlimit = options.output_limit.or(outputlimit);
+
+    // The command runs in `scratch` if any of them is `Some`.
+    let scratch_file = |name: &str| scratch.expect("should be `Some`").join(name);
+
+    if let Some(inputfile) = &inputfile {
+        let path = scratch_file(inputfile);
+        tokio::task::block_in_place(|| std::fs::write(path, stdin.as_bytes()))?;
+    }
+
+    let started = Instant::now();
+
+    let mut child = Child::spawn(&mut cmd).map_err(|e| resource_limits.context(e))?;
+
+    // Write and read at the same time. Otherwise the solution may block on a full pipe.
+    let stdin_writer = tokio::task::spawn(write_and_close(
+        child.stdin.take(),
+        if inputfile.is_some() {
+            "".into()
+        } else {
+            stdin.clone()
+        },
+    ));
+    let (outputlimit_tx, mut outputlimit_rx) = tokio::sync::mpsc::unbounded_channel();
+    let stdout_reader = tokio::task::spawn(read_to_end_with_limit(
+        child.stdout.take(),
+        outputlimit,
+        outputlimit_tx.clone(),
+    ));
+    let stderr_reader = tokio::task::spawn(read_to_end_with_limit(
+        child.stderr.take(),
+        outputlimit,
+        outputlimit_tx,
+    ));
+
+    macro_rules! with_ctrl_c {
+        ($future:expr) => {
+            select! {
+                __output = $future => __output,
+                interruption = ctrl_c_rx.recv().fuse() => {
+                    kill_tree(&mut child);
+                    return Err(interruption?.into());
+                },
+            }
+        };
+    }
+
+    let wait = async {
+        select! {
+            status = wait_with_usage(&mut child).fuse() => status.map(Some),
+            () = output_limit_exceeded(&mut outputlimit_rx).fuse() => Ok(None),
+        }
+    };
+
+    let status = if let Some(timelimit) = timelimit {
+        let timeout = options.timeout(timelimit);
+
+        if let Ok(status) = with_ctrl_c!(tokio::time::timeout(timeout, wait).fuse()) {
+            status?
+        } else {
+            kill_tree(&mut child);
+            return Ok(Verdict::TimelimitExceeded {
+                test_case_name,
+                timelimit,
+                cpu_time: None,
+                stdin,
+                expected,
+                memory: None,
+            });
+        }
+    } else {
+        with_ctrl_c!(wait.fuse())?
+    };
+
+    let elapsed = Instant::now() - started;
+
+    let (
+        status,
+        Usage {
+            max_rss: memory,
+            cpu_time,
+        },
+    ) = if let Some(status) = status {
+        status
+    } else {
+        kill_tree(&mut child);
+        let (stdout, _) = stdout_reader.await??;
+        let (stderr, _) = stderr_reader.await??;
+        return Ok(Verdict::OutputLimitExceeded {
+            test_case_name,
+            elapsed,
+            cpu_time: None,
+            stdin,
+            stdout: stdout.into(),
+            stderr: stderr.into(),
+            expected,
+            memory: None,
+            outputlimit: outputlimit.expect("should be `Some`"),
+        });
+    };
+
+    // Descendants may still hold the pipes open.
+    kill_process_group(child.id());
+
+    stdin_writer.await??;
+    let (stdout, stdout_exceeded) = stdout_reader.await??;
+    let (stderr, stderr_exceeded) = stderr_reader.await??;
+    let (stdout, stderr) = (Arc::<str>::from(stdout), Arc::<str>::from(stderr));
+
+    // The process may exit before `output_limit_exceeded` is notified.
+    if stdout_exceeded || stderr_exceeded {
+        return Ok(Verdict::OutputLimitExceeded {
+            test_case_name,
+            elapsed,
+            cpu_time,
+            stdin,
+            stdout,
+            stderr,
+            expected,
+            memory,
+            outputlimit: outputlimit.expect("should be `Some`"),
+        });
+    }
+
+    let stdout = if let Some(outputfile) = &outputfile {
+        // A missing file is judged as an empty output.
+        let path = scratch_file(outputfile);
+        let output = tokio::task::block_in_place(|| std::fs::read(path)).unwrap_or_default();
+        let output = Arc::<str>::from(String::from_utf8_lossy(&output));
+
+        if matches!(outputlimit, Some(l) if output.len() as u64 > l) {
+            return Ok(Verdict::OutputLimitExceeded {
+                test_case_name,
+                elapsed,
+                cpu_time,
+                stdin,
+                stdout: output,
+                stderr,
+                expected,
+                memory,
+                outputlimit: outputlimit.unwrap(),
+            });
+        }
+
+        output
+    } else {
+        stdout
+    };
+
+    let verdict = if matches!(timelimit, Some(t) if options.exceeds(t, elapsed, cpu_time)) {
+        Verdict::TimelimitExceeded {
+            test_case_name,
+            timelimit: timelimit.unwrap(),
+            cpu_time,
+            stdin,
+            expected,
+            memory,
+        }
+    } else if let Some(reason) = options.sandbox.and(sandbox::violation(status)) {
+        Verdict::SandboxViolation {
+            test_case_name,
+            elapsed,
+            cpu_time,
+            stdin,
+            stdout,
+            stderr,
+            expected,
+            status,
+            memory,
+            reason,
+        }
+    } else if matches!((memory, memorylimit), (Some(m), Some(l)) if m > l) {
+        Verdict::MemoryLimitExceeded {
+            test_case_name,
+            elapsed,
+            cpu_time,
+            stdin,
+            stdout,
+            stderr,
+            expected,
+            memory: memory.unwrap(),
+            memorylimit: memorylimit.unwrap(),
+        }
+    } else if !status.success() {
+        Verdict::RuntimeError {
+            test_case_name,
+            elapsed,
+            cpu_time,
+            stdin,
+            stdout,
+            stderr,
+            expected,
+            status,
+            memory,
+            judge_message: None,
+            stack_size: diagnosis::stack_size(&resource_limits),
+        }
+    } else {
+        let (accepted, judge_message) = if let ExpectedOutput::Checker { text, command } = &expected
+        {
+            select! {
+                result = check(command, &stdin, &stdout, text.as_deref()).fuse() => result?,
+                interruption = ctrl_c_rx.recv().fuse() => return Err(interruption?.into()),
+            }
+        } else {
+            (expected.accepts(&stdout), None)
+        };
+
+        if accepted {
+            Verdict::Accepted {
+                test_case_name,
+                elapsed,
+                cpu_time,
+                stdin,
+                stdout,
+                stderr,
+                expected,
+                status,
+                memory,
+                judge_message,
+            }
+        } else {
+            Verdict::WrongAnswer {
+                test_case_name,
+                elapsed,
+                cpu_time,
+                stdin,
+                stdout,
+                stderr,
+                expected,
+                status,
+                memory,
+                judge_message,
+            }
+        }
+    };
+
+    return Ok(verdict);
+
+    async fn read_to_end_with_limit(
+        src: Option<impl AsyncRead + Unpin>,
+        limit: Option<u64>,
+        on_exceeded: tokio::sync::mpsc::UnboundedSender<()>,
+    ) -> io::Result<(String, bool)> {
+        let mut buf = vec![];
+        let mut exceeded = false;
+
+        if let Some(mut src) = src {
+            if let Some(limit) = limit {
+                // Read one more byte to tell whether the output exceeds the limit.
+                src.take(limit + 1).read_to_end(&mut buf).await?;
+
+                if buf.len() as u64 > limit {
+                    buf.truncate(limit as _);
+                    exceeded = true;
+                    let _ = on_exceeded.send(());
+                }
+            } else {
+                src.read_to_end(&mut buf).await?;
+            }
+        }
+
+        Ok((String::from_utf8_lossy(&buf).into_owned(), exceeded))
+    }
+
+    async fn output_limit_exceeded(rx: &mut tokio::sync::mpsc::UnboundedReceiver<()>) {
+        // `None` means both of stdout and stderr have been closed within the limit.
+        if rx.recv().await.is_none() {
+            futures_util::future::pending().await
+        }
+    }
+
+    async fn check(
+        checker: &CommandExpression,
+        input: &str,
+        actual: &str,
+        expected: Option<&str>,
+    ) -> anyhow::Result<(bool, Option<Arc<str>>)> {
+        // A checker should not take long. Generous for slow interpreters.
+        const TIMEOUT: Duration = Duration::from_secs(10);
+
+        let (dir, paths) = tokio::task::block_in_place(|| -> io::Result<_> {
+            let dir = tempfile::Builder::new()
+                .prefix("snowchains-checker")
+                .tempdir()?;
+
+            let input_path = dir.path().join("input.txt");
+            let actual_path = dir.path().join("output.txt");
+            let expected_path = dir.path().join("answer.txt");
+
+            std::fs::write(&input_path, input)?;
+            std::fs::write(&actual_path, actual)?;
+            std::fs::write(&expected_path, expected.unwrap_or(""))?;
+
+            Ok((dir, [input_path, actual_path, expected_path]))
+        })?;
+
+        let output = checker
+            .build()
+            .args(&paths)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+
+        let output = tokio::time::timeout(TIMEOUT, output)
+            .await
+            .map_err(|_| anyhow!("The checker timed out after {} s", TIMEOUT.as_secs()))?
+            .with_context(|| format!("Could not execute the checker: {:?}", checker.program))?;
+
+        tokio::task::block_in_place(|| dir.close())?;
+
+        let message = [&output.stdout, &output.stderr]
+            .iter()
+            .map(|s| String::from_utf8_lossy(s))
+            .collect::<String>();
+
+        // testlib exits with 1 for `WA`, 2 for `PE`, and 3 for `FAIL` (a bug of the checker).
+        let accepted = match output.status.code() {
+            Some(0) => true,
+            Some(1) | Some(2) => false,
+            _ => bail!(
+                "The checker failed ({}): {}",
+                diagnosis::describe_status(output.status),
+                message.trim_end(),
+            ),
+        };
+
+        Ok((accepted, Some(message.into())))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Usage {
+    max_rss: Option<u64>,
+    cpu_time: Option<Duration>,
+}
+
+#[cfg(unix)]
+async fn wait_with_usage(child: &mut Child) -> io::Result<(ExitStatus, Usage)> {
+    use std::{mem::MaybeUninit, os::unix::process::ExitStatusExt as _};
+
+    let pid = child.id() as libc::pid_t;
+
+    // `tokio::process::Child` does not expose `rusage`, so reap the child by ourselves.
+    let reaper = child.reaper.clone();
+    {
+        let mut reaper = reaper.lock().unwrap();
+        if *reaper == Reaper::Reaped {
+            return Err(io::Error::new(io::ErrorKind::Other, "already reaped"));
+        }
+        *reaper = Reaper::WaitWithUsage;
+    }
+
+    tokio::task::spawn_blocking(move || {
+        // Wait without reaping, so that `kill_tree` can signal the pid until we reap it below.
+        loop {
+            let mut info = MaybeUninit::<libc::siginfo_t>::zeroed();
+            let options = libc::WEXITED | libc::WNOWAIT;
+            if unsafe { libc::waitid(libc::P_PID, pid as _, info.as_mut_ptr(), options) } == 0 {
+                break;
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+
+        let mut reaper = reaper.lock().unwrap();
+
+        let mut status = 0;
+        let mut rusage = MaybeUninit::<libc::rusage>::zeroed();
+
+        if unsafe { libc::wait4(pid, &mut status, libc::WNOHANG, rusage.as_mut_ptr()) } != pid {
+            return Err(io::Error::last_os_error());
+        }
+
+        *reaper = Reaper::Reaped;
+
+        let rusage = unsafe { rusage.assume_init() };
+
+        // `ru_maxrss` is in bytes on macOS and in kilobytes elsewhere.
+        let max_rss = if cfg!(any(target_os = "macos", target_os = "ios")) {
+            rusage.ru_maxrss as u64
+        } else {
+            rusage.ru_maxrss as u64 * 1024
+        };
+
+        let cpu_time = [rusage.ru_utime, rusage.ru_stime]
+            .iter()
+            .map(|t| Duration::new(t.tv_sec as _, t.tv_usec as u32 * 1000))
+            .sum();
+
+        let usage = Usage {
+            max_rss: Some(max_rss),
+            cpu_time: Some(cpu_time),
+        };
+
+        Ok((ExitStatus::from_raw(status), usage))
+    })
+    .await?
+}
+
+#[cfg(not(unix))]
+async fn wait_with_usage(child: &mut Child) -> io::Result<(ExitStatus, Usage)> {
+    Ok(((&mut *child.inner).await?, Usage::default()))
+}
+
+/// Runs the solution and the interactor with their stdin/stdout cross-wired.
+///
+/// The interactor receives the path of a file containing the case input as its last argument.
+/// What the solution read and wrote are recorded as `stdin` and `stdout` of the verdict.
+async fn judge_interactive(
+    mut cmd: tokio::process::Command,
+    test_case: InteractiveTestCase,
+    resource_limits: ResourceLimits,
+    options: JudgeOptions,
+    ctrl_c_rx: &mut tokio::sync::broadcast::Receiver<Interruption>,
+) -> anyhow::Result<Verdict> {
+    let InteractiveTestCase {
+        name: test_case_name,
+        timelimit,
+        input,
+        interactor,
+    } = test_case;
+
+    let expected = ExpectedOutput::Pass;
+
+    let mut input_file = tempfile::Builder::new()
+        .prefix("snowchains-interactive")
+        .suffix(".txt")
+        .tempfile()?;
+    io::Write::write_all(&mut input_file, (*input).as_ref())?;
+
+    let mut interactor = interactor.build();
+    interactor.arg(input_file.path());
+
+    let started = Instant::now();
+
+    let mut solution = Child::spawn(&mut cmd).map_err(|e| resource_limits.context(e))?;
+    let mut interactor = Child::spawn(&mut interactor)?;
+
+    let to_solution = tokio::task::spawn(forward(interactor.stdout.take(), solution.stdin.take()));
+    let to_interactor =
+        tokio::task::spawn(forward(solution.stdout.take(), interactor.stdin.take()));
+    let solution_stderr = tokio::task::spawn(read_to_end(solution.stderr.take()));
+    let interactor_stderr = tokio::task::spawn(read_to_end(interactor.stderr.take()));
+
+    macro_rules! with_ctrl_c {
+        ($future:expr) => {
+            select! {
+                __output = $future => __output,
+                interruption = ctrl_c_rx.recv().fuse() => {
+                    kill_tree(&mut solution);
+                    kill_tree(&mut interactor);
+                    return Err(interruption?.into());
+                },
+            }
+        };
+    }
+
+    let timeout = timelimit.map(|t| options.timeout(t));
+
+    let solution_status = if let Some(timeout) = timeout {
+        match with_ctrl_c!(tokio::time::timeout(timeout, wait_with_usage(&mut solution)).fuse()) {
+            Ok(status) => Some(status?),
+            Err(_) => None,
+        }
+    } else {
+        Some(with_ctrl_c!(wait_with_usage(&mut solution).fuse())?)
+    };
+
+    let elapsed = Instant::now() - started;
+
+    let interactor_status = if let Some(timeout) = timeout {
+        let rest = timeout.checked_sub(elapsed).unwrap_or_default();
+        match with_ctrl_c!(tokio::time::timeout(rest, &mut *interactor).fuse()) {
+            Ok(status) => Some(status?),
+            Err(_) => None,
+        }
+    } else {
+        Some(with_ctrl_c!((&mut *interactor).fuse())?)
+    };
+
+    let (
+        (
+            solution_status,
+            Usage {
+                max_rss: memory,
+                cpu_time,
+            },
+        ),
+        interactor_status,
+    ) = match (solution_status, interactor_status) {
+        (Some(solution_status), Some(interactor_status)) => (solution_status, interactor_status),
+        _ => {
+            kill_tree(&mut solution);
+            kill_tree(&mut interactor);
+            return Ok(Verdict::TimelimitExceeded {
+                test_case_name,
+                timelimit: timelimit.expect("should be `Some`"),
+                cpu_time: None,
+                stdin: input,
+                expected,
+                memory: None,
+            });
+        }
+    };
+
+    // Descendants may still hold the pipes open.
+    kill_process_group(solution.id());
+    kill_process_group(interactor.id());
+
+    let stdin = Arc::from(to_solution.await??);
+    let stdout = Arc::from(to_interactor.await??);
+    let stderr = Arc::from(solution_stderr.await??);
+    let judge_message = Some(Arc::from(interactor_stderr.await??));
+
+    input_file.close()?;
+
+    let verdict = if matches!(timelimit, Some(t) if options.exceeds(t, elapsed, cpu_time)) {
+        Verdict::TimelimitExceeded {
+            test_case_name,
+            timelimit: timelimit.unwrap(),
+            cpu_time,
+            stdin,
+            expected,
+            memory,
+        }
+    } else if let Some(reason) = options.sandbox.and(sandbox::violation(solution_status)) {
+        Verdict::SandboxViolation {
+            test_case_name,
+            elapsed,
+            cpu_time,
+            stdin,
+            stdout,
+            stderr,
+            expected,
+            status: solution_status,
+            memory,
+            reason,
+        }
+    } else if !solution_status.success() {
+        Verdict::RuntimeError {
+            test_case_name,
+            elapsed,
+            cpu_time,
+            stdin,
+            stdout,
+            stderr,
+            expected,
+            status: solution_status,
+            memory,
+            judge_message,
+            stack_size: diagnosis::stack_size(&resource_limits),
+        }
+    } else if !interactor_status.success() {
+        Verdict::WrongAnswer {
+            test_case_name,
+            elapsed,
+            cpu_time,
+            stdin,
+            stdout,
+            stderr,
+            expected,
+            status: solution_status,
+            memory,
+            judge_message,
+        }
+    } else {
+        Verdict::Accepted {
+            test_case_name,
+            elapsed,
+            cpu_time,
+            stdin,
+            stdout,
+            stderr,
+            expected,
+            status: solution_status,
+            memory,
+            judge_message,
+        }
+    };
+
+    return Ok(verdict);
+
+    async fn forward(
+        src: Option<impl AsyncRead + Unpin>,
+        mut dst: Option<impl AsyncWrite + Unpin>,
+    ) -> io::Result<String> {
+        let mut transcript = vec![];
+
+        if let Some(mut src) = src {
+            let mut buf = [0; 4096];
+
+            loop {
+                let n = src.read(&mut buf).await?;
+                if n == 0 {
+                    break;
+                }
+                transcript.extend_from_slice(&buf[..n]);
+
+                // The other side may have exited. Keep reading so that the writer does not block.
+                if let Some(w) = &mut dst {
+                    if w.write_all(&buf[..n]).await.is_err() || w.flush().await.is_err() {
+                        dst = None;
+                    }
+                }
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&transcript).into_owned())
+    }
+}
+
+async fn write_and_close(dst: Option<impl AsyncWrite + Unpin>, text: Arc<str>) -> io::Result<()> {
+    if let Some(mut dst) = dst {
+        // The process may exit without reading all of the input.
+        match dst.write_all(text.as_bytes()).await {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+async fn read_to_end(src: Option<impl AsyncRead + Unpin>) -> io::Result<String> {
+    let mut buf = vec![];
+    if let Some(mut src) = src {
+        src.read_to_end(&mut buf).await?;
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandExpression, JudgeOptions, JudgeOutcome, ResourceLimits, Verdict};
+    use crate::testsuite::{BatchTestCase, ExpectedOutput, InteractiveTestCase, TestCase};
+    use indicatif::ProgressDrawTarget;
+    use maplit::btreemap;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::{env, os::unix::process::ExitStatusExt as _, process::ExitStatus, time::Duration};
+
+    fn sh(script: &str) -> CommandExpression {
+        CommandExpression {
+            program: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            cwd: env::temp_dir(),
+            env: btreemap!(),
+            resource_limits: ResourceLimits::default(),
+        }
+    }
+
+    fn judge(cmd: &CommandExpression, test_cases: &[TestCase]) -> JudgeOutcome {
+        super::judge(
+            ProgressDrawTarget::hidden(),
+            futures_util::future::pending,
+            cmd,
+            test_cases,
+            JudgeOptions::default(),
+        )
+        .unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn checker_exit_codes() {
+        let test_case = |checker: &str| {
+            TestCase::Batch(BatchTestCase {
+                name: None,
+                timelimit: Some(Duration::from_secs(10)),
+                memorylimit: None,
+                outputlimit: None,
+                inputfile: None,
+                outputfile: None,
+                input: "".into(),
+                output: ExpectedOutput::Checker {
+                    text: None,
+                    command: sh(checker),
+                },
+            })
+        };
+
+        let outcome = judge(&sh("echo"), &[test_case("exit 0"), test_case("exit 1")]);
+        assert!(matches!(outcome.verdicts[0], Verdict::Accepted { .. }));
+        assert!(matches!(outcome.verdicts[1], Verdict::WrongAnswer { .. }));
+
+        // `FAIL` of testlib.
+        assert!(super::judge(
+            ProgressDrawTarget::hidden(),
+            futures_util::future::pending,
+            &sh("echo"),
+            &[test_case("exit 3")],
+            JudgeOptions::default(),
+        )
+        .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn interactive() {
+        // The interactor receives the path to the input file as `$0`.
+        let test_case = TestCase::Interactive(InteractiveTestCase {
+            name: None,
+            timelimit: Some(Duration::from_secs(10)),
+            input: "42\n".into(),
+            interactor: sh(r#"read -r n < "$0"; echo "$n"; read -r reply; test "$reply" = "$n""#),
+        });
+
+        let outcome = judge(&sh("read -r x; echo \"$x\""), &[test_case.clone()]);
+        assert!(matches!(outcome.verdicts[0], Verdict::Accepted { .. }));
+
+        let outcome = judge(&sh("read -r x; echo \"$((x + 1))\""), &[test_case]);
+        assert!(matches!(outcome.verdicts[0], Verdict::WrongAnswer { .. }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_io() {
+        let test_case = TestCase::Batch(BatchTestCase {
+            name: None,
+            timelimit: Some(Duration::from_secs(10)),
+            memorylimit: None,
+            outputlimit: None,
+            inputfile: Some("input.txt".to_owned()),
+            outputfile: Some("output.txt".to_owned()),
+            input: "42\n".into(),
+            output: ExpectedOutput::Exact {
+                text: "42\n".into(),
+            },
+        });
+
+        let outcome = judge(&sh("cat input.txt > output.txt"), &vec![test_case; 4]);
+        assert!(outcome
+            .verdicts
+            .iter()
+            .all(|v| matches!(v, Verdict::Accepted { .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resource_limits_capped_at_hard_limits() {
+        let test_case = TestCase::Batch(BatchTestCase {
+            name: None,
+            timelimit: Some(Duration::from_secs(10)),
+            memorylimit: None,
+            outputlimit: None,
+            inputfile: None,
+            outputfile: None,
+            input: "".into(),
+            output: ExpectedOutput::Pass,
+        });
+
+        let mut cmd = sh(r#"test "$(ulimit -Sn)" = "$(ulimit -Hn)""#);
+        cmd.resource_limits.open_files = Some(u64::MAX);
+
+        let outcome = judge(&cmd, &[test_case]);
+        assert!(matches!(outcome.verdicts[0], Verdict::Accepted { .. }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_limit_exceeded_on_exit() {
+        let test_case = TestCase::Batch(BatchTestCase {
+            name: None,
+            timelimit: Some(Duration::from_secs(10)),
+            memorylimit: None,
+            outputlimit: Some(10),
+            inputfile: None,
+            outputfile: None,
+            input: "".into(),
+            output: ExpectedOutput::Pass,
+        });
+
+        // The process exits right after writing past the limit.
+        let outcome = judge(&sh("head -c 100 /dev/zero"), &vec![test_case; 16]);
+
+        for verdict in outcome.verdicts {
+            assert!(matches!(verdict, Verdict::OutputLimitExceeded { .. }));
+        }
+    }
+
+    #[test]
+    fn error_on_fail() {
+        let tle = || Verdict::TimelimitExceeded {
+            test_case_name: None,
+            timelimit: Duration::from_secs(2),
+            cpu_time: None,
+            stdin: "".into(),
+            expected: ExpectedOutput::Pass,
+            memory: None,
+        };
+
+        let skipped = || Verdict::Skipped {
+            test_case_name: None,
+            stdin: "".into(),
+            expected: ExpectedOutput::Pass,
+        };
+
+        let message = |verdicts| {
+            JudgeOutcome { verdicts }
+                .error_on_fail()
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            "1/1 test failed (2 skipped)",
+            message(vec![tle(), skipped(), skipped()])
+        );
+        assert_eq!("2/2 tests failed", message(vec![tle(), tle()]));
+    }
+
+    #[test]
+    fn outcome_json() {
+        let outcome = JudgeOutcome {
+            verdicts: vec![
+                Verdict::WrongAnswer {
+                    test_case_name: Some("1".to_owned()),
+                    elapsed: Duration::from_millis(12),
+                    cpu_time: Some(Duration::from_millis(10)),
+                    stdin: "1 2\n".into(),
+                    stdout: "ああ\n".into(),
+                    stderr: "".into(),
+                    expected: ExpectedOutput::Exact { text: "3\n".into() },
+                    status: ExitStatus::from_raw(0),
+                    memory: None,
+                    judge_message: None,
+                },
+                Verdict::TimelimitExceeded {
+                    test_case_name: None,
+                    timelimit: Duration::from_secs(2),
+                    cpu_time: None,
+                    stdin: "".into(),
+                    expected: ExpectedOutput::Pass,
+                    memory: Some(1024),
+                },
+            ],
+        };
+
+        let text = |text: &str, truncated: bool| json!({ "text": text, "truncated": truncated });
+
+        assert_eq!(
+            json!({
+                "verdicts": [
+                    {
+                        "test_case_name": "1",
+                        "verdict": "WrongAnswer",
+                        "elapsed_ms": 12,
+                        "cpu_time_ms": 10,
+                        "memory": null,
+                        "timelimit_ms": null,
+                        "memorylimit": null,
+                        "outputlimit": null,
+                        "exit_status": { "success": true, "code": 0, "signal": null },
+                        "stdin": text("1 2\n", false),
+                        "stdout": text("あ", true),
+                        "stderr": text("", false),
+                        "expected": text("3\n", false),
+                        "judge_message": null,
+                    },
+                    {
+                        "test_case_name": null,
+                        "verdict": "TimelimitExceeded",
+                        "elapsed_ms": null,
+                        "cpu_time_ms": null,
+                        "memory": 1024,
+                        "timelimit_ms": 2000,
+                        "memorylimit": null,
+                        "outputlimit": null,
+                        "exit_status": null,
+                        "stdin": text("", false),
+                        "stdout": null,
+                        "stderr": null,
+                        "expected": null,
+                        "judge_message": null,
+                    },
+                ],
+            }),
+            serde_json::to_value(outcome.with_text_limit(Some(4))).unwrap(),
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use super::{truncate, JudgeOutcome, Verdict};
+use serde::Serialize;
+use std::{borrow::Cow, io, time::Duration};
+
+pub(super) fn write_junit(
+    mut wtr: impl io::Write,
+    outcome: &JudgeOutcome,
+    name: &str,
+    text_limit: Option<usize>,
+) -> io::Result<()> {
+    let verdicts = &outcome.verdicts;
+
+    let failures = verdicts.iter().filter(|v| is_failure(v)).count();
+    let skipped = verdicts
+        .iter()
+        .filter(|v| matches!(v, Verdict::Skipped { .. }))
+        .count();
+    let time = verdicts.iter().map(time).sum();
+
+    writeln!(wtr, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(wtr, "<testsuites>")?;
+    writeln!(
+        wtr,
+        r#"  <testsuite name="{}" tests="{}" failures="{}" errors="0" skipped="{}" time="{}">"#,
+        escape_xml(name),
+        verdicts.len(),
+        failures,
+        skipped,
+        secs(time),
+    )?;
+
+    for (i, verdict) in verdicts.iter().enumerate() {
+        write!(
+            wtr,
+            r#"    <testcase name="{}" classname="{}" time="{}""#,
+            escape_xml(&test_case_name(i, verdict)),
+            escape_xml(name),
+            secs(self::time(verdict)),
+        )?;
+
+        match verdict {
+            Verdict::Accepted { .. } => writeln!(wtr, "/>")?,
+            Verdict::Skipped { .. } => {
+                writeln!(wtr, ">")?;
+                writeln!(wtr, "      <skipped/>")?;
+                writeln!(wtr, "    </testcase>")?;
+            }
+            _ => {
+                writeln!(wtr, ">")?;
+                writeln!(
+                    wtr,
+                    r#"      <failure type="{}" message="{}">{}</failure>"#,
+                    verdict.kind(),
+                    escape_xml(&verdict.summary()),
+                    escape_xml(&excerpt(verdict, text_limit)),
+                )?;
+                writeln!(wtr, "    </testcase>")?;
+            }
+        }
+    }
+
+    writeln!(wtr, "  </testsuite>")?;
+    writeln!(wtr, "</testsuites>")?;
+    wtr.flush()
+}
+
+pub(super) fn write_tap(
+    mut wtr: impl io::Write,
+    outcome: &JudgeOutcome,
+    text_limit: Option<usize>,
+) -> io::Result<()> {
+    writeln!(wtr, "TAP version 13")?;
+    writeln!(wtr, "1..{}", outcome.verdicts.len())?;
+
+    for (i, verdict) in outcome.verdicts.iter().enumerate() {
+        let description = test_case_name(i, verdict).replace('#', r"\#");
+
+        if let Verdict::Skipped { .. } = verdict {
+            writeln!(wtr, "ok {} - {} # SKIP", i + 1, description)?;
+            continue;
+        }
+
+        if is_failure(verdict) {
+            writeln!(wtr, "not ok {} - {}", i + 1, description)?;
+        } else {
+            writeln!(wtr, "ok {} - {}", i + 1, description)?;
+        }
+
+        let text = |text| truncate_with_ellipsis(text, text_limit);
+
+        let failed = is_failure(verdict);
+
+        let diagnostics = serde_yaml::to_string(&TapDiagnostics {
+            message: verdict.summary(),
+            verdict: verdict.kind(),
+            duration_ms: time(verdict).as_millis() as u64,
+            expected: verdict
+                .expected()
+                .text()
+                .filter(|_| failed)
+                .map(|s| text(s)),
+            actual: verdict.stdout().filter(|_| failed).map(text),
+            stderr: verdict
+                .stderr()
+                .filter(|s| failed && !s.is_empty())
+                .map(text),
+            judge: verdict.judge_message().map(text),
+        })
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+        writeln!(wtr, "  ---")?;
+        for line in diagnostics.lines().filter(|&l| l != "---") {
+            writeln!(wtr, "  {}", line)?;
+        }
+        writeln!(wtr, "  ...")?;
+    }
+
+    wtr.flush()
+}
+
+#[derive(Serialize)]
+struct TapDiagnostics<'a> {
+    message: String,
+    verdict: &'static str,
+    duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actual: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stderr: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    judge: Option<Cow<'a, str>>,
+}
+
+fn truncate_with_ellipsis(text: &str, limit: Option<usize>) -> Cow<'_, str> {
+    match truncate(text, limit) {
+        (text, true) => format!("{}...", text).into(),
+        (text, false) => text.into(),
+    }
+}
+
+fn is_failure(verdict: &Verdict) -> bool {
+    !matches!(verdict, Verdict::Accepted { .. } | Verdict::Skipped { .. })
+}
+
+fn test_case_name(i: usize, verdict: &Verdict) -> String {
+    verdict
+        .test_case_name()
+        .map(ToOwned::to_owned)
+        .unwrap_or_else(|| (i + 1).to_string())
+}
+
+/// Elapsed time, or the timelimit for `TimelimitExceeded`.
+fn time(verdict: &Verdict) -> Duration {
+    match *verdict {
+        Verdict::TimelimitExceeded { timelimit, .. } => timelimit,
+        _ => verdict.elapsed().unwrap_or_default(),
+    }
+}
+
+fn secs(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64())
+}
+
+/// Expected and actual outputs, stderr, and the judge message of a failed test case.
+fn excerpt(verdict: &Verdict, text_limit: Option<usize>) -> String {
+    let mut acc = "".to_owned();
+
+    for &(header, text) in &[
+        ("expected", verdict.expected().text().map(|s| &**s)),
+        ("actual", verdict.stdout()),
+        ("stderr", verdict.stderr().filter(|s| !s.is_empty())),
+        ("judge", verdict.judge_message()),
+    ] {
+        if let Some(text) = text {
+            acc += header;
+            acc += ":\n";
+            acc += &truncate_with_ellipsis(text, text_limit);
+            if !acc.ends_with('\n') {
+                acc += "\n";
+            }
+        }
+    }
+
+    acc
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut acc = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => acc += "&amp;",
+            '<' => acc += "&lt;",
+            '>' => acc += "&gt;",
+            '"' => acc += "&quot;",
+            '\'' => acc += "&apos;",
+            '\t' | '\n' | '\r' => acc.push(c),
+            // Not allowed in XML 1.0 even as character references.
+            '\u{0}'..='\u{1f}' => acc.push('\u{fffd}'),
+            c => acc.push(c),
+        }
+    }
+    acc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_xml, JudgeOutcome, Verdict};
+    use crate::testsuite::ExpectedOutput;
+    use pretty_assertions::assert_eq;
+    use std::{os::unix::process::ExitStatusExt as _, process::ExitStatus, time::Duration};
+
+    fn outcome() -> JudgeOutcome {
+        JudgeOutcome {
+            verdicts: vec![
+                Verdict::Accepted {
+                    test_case_name: Some("sample1".to_owned()),
+                    elapsed: Duration::from_millis(12),
+                    cpu_time: None,
+                    stdin: "1 2\n".into(),
+                    stdout: "3\n".into(),
+                    stderr: "".into(),
+                    expected: ExpectedOutput::Exact { text: "3\n".into() },
+                    status: ExitStatus::from_raw(0),
+                    memory: None,
+                    judge_message: None,
+                },
+                Verdict::WrongAnswer {
+                    test_case_name: Some("a<b>#2".to_owned()),
+                    elapsed: Duration::from_millis(1500),
+                    cpu_time: None,
+                    stdin: "2 2\n".into(),
+                    stdout: "5\n".into(),
+                    stderr: "debug\n".into(),
+                    expected: ExpectedOutput::Exact { text: "4\n".into() },
+                    status: ExitStatus::from_raw(0),
+                    memory: None,
+                    judge_message: None,
+                },
+                Verdict::TimelimitExceeded {
+                    test_case_name: None,
+                    timelimit: Duration::from_secs(2),
+                    cpu_time: None,
+                    stdin: "".into(),
+                    expected: ExpectedOutput::Pass,
+                    memory: None,
+                },
+                Verdict::Skipped {
+                    test_case_name: Some("sample4".to_owned()),
+                    stdin: "".into(),
+                    expected: ExpectedOutput::Pass,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn junit() {
+        let mut junit = vec![];
+        outcome().write_junit(&mut junit, "abc100_a", None).unwrap();
+
+        assert_eq!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="abc100_a" tests="4" failures="2" errors="0" skipped="1" time="3.512">
+    <testcase name="sample1" classname="abc100_a" time="0.012"/>
+    <testcase name="a&lt;b&gt;#2" classname="abc100_a" time="1.500">
+      <failure type="WrongAnswer" message="Wrong Answer (1500 ms)">expected:
+4
+actual:
+5
+stderr:
+debug
+</failure>
+    </testcase>
+    <testcase name="3" classname="abc100_a" time="2.000">
+      <failure type="TimelimitExceeded" message="Timelimit Exceeded (2000 ms)"></failure>
+    </testcase>
+    <testcase name="sample4" classname="abc100_a" time="0.000">
+      <skipped/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#,
+            String::from_utf8(junit).unwrap(),
+        );
+    }
+
+    #[test]
+    fn tap() {
+        let mut tap = vec![];
+        outcome().write_tap(&mut tap, Some(2)).unwrap();
+
+        assert_eq!(
+            r#"TAP version 13
+1..4
+ok 1 - sample1
+  ---
+  message: Accepted (12 ms)
+  verdict: Accepted
+  duration_ms: 12
+  ...
+not ok 2 - a<b>\#2
+  ---
+  message: Wrong Answer (1500 ms)
+  verdict: WrongAnswer
+  duration_ms: 1500
+  expected: "4\n"
+  actual: "5\n"
+  stderr: de...
+  ...
+not ok 3 - 3
+  ---
+  message: Timelimit Exceeded (2000 ms)
+  verdict: TimelimitExceeded
+  duration_ms: 2000
+  ...
+ok 4 - sample4 # SKIP
+"#,
+            String::from_utf8(tap).unwrap(),
+        );
+    }
+
+    #[test]
+    fn escape_xml_special_chars() {
+        assert_eq!(
+            "a &lt;b&gt; &amp; &quot;c&quot; &apos;d&apos;\n\u{fffd}",
+            escape_xml("a <b> & \"c\" 'd'\n\u{1b}"),
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use super::SandboxOptions;
+use std::{io, path::Path, process::ExitStatus};
+
+/// Isolates solutions with unprivileged user, mount and network namespaces.
+///
+/// Every mount is made read-only except the scratch directory of the process, which is also set to
+/// `$TMPDIR`. Everything the child needs is prepared before `fork`, so that the child only issues
+/// system calls.
+#[cfg(target_os = "linux")]
+pub(super) struct Sandbox {
+    setup: std::sync::Arc<linux::Setup>,
+}
+
+#[cfg(target_os = "linux")]
+impl Sandbox {
+    pub(super) fn new(options: SandboxOptions) -> anyhow::Result<Self> {
+        Ok(Self {
+            setup: linux::Setup::new(options)?.into(),
+        })
+    }
+
+    /// Confines `cmd`. `scratch` stays writable.
+    pub(super) fn apply(
+        &self,
+        cmd: &mut tokio::process::Command,
+        scratch: &Path,
+    ) -> io::Result<()> {
+        let setup = self.setup.clone();
+        let scratch_path = linux::c_string(scratch.as_os_str())?;
+
+        cmd.env("TMPDIR", scratch);
+
+        unsafe {
+            cmd.pre_exec(move || setup.enter(&scratch_path));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(super) enum Sandbox {}
+
+#[cfg(not(target_os = "linux"))]
+impl Sandbox {
+    pub(super) fn new(_: SandboxOptions) -> anyhow::Result<Self> {
+        anyhow::bail!("The sandbox is supported only on Linux");
+    }
+
+    pub(super) fn apply(&self, _: &mut tokio::process::Command, _: &Path) -> io::Result<()> {
+        match *self {}
+    }
+}
+
+/// Tells whether the process was killed for exceeding the file size limit of the sandbox.
+///
+/// Breaking the other limits cannot be told from ordinary failures. `fork` fails with `EAGAIN` at
+/// the process limit, and allocations fail with `ENOMEM` at the address space limit. They end up as
+/// `RuntimeError`.
+pub(super) fn violation(status: ExitStatus) -> Option<&'static str> {
+    #[cfg(unix)]
+    {
+        match super::diagnosis::signal(status) {
+            Some(libc::SIGXFSZ) => Some("file size limit exceeded"),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = status;
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::SandboxOptions;
+    use std::{
+        ffi::{CStr, CString, OsStr},
+        fs, io, mem,
+        os::unix::ffi::OsStrExt as _,
+        ptr,
+    };
+
+    pub(super) struct Setup {
+        uid_map: CString,
+        gid_map: CString,
+        mount_points: Vec<CString>,
+        options: SandboxOptions,
+    }
+
+    impl Setup {
+        pub(super) fn new(options: SandboxOptions) -> anyhow::Result<Self> {
+            let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+
+            // Keep the IDs, except root, which would be able to undo the read-only mounts.
+            let map = |id| {
+                let inner = if id == 0 { 65534 } else { id };
+                CString::new(format!("{} {} 1\n", inner, id)).expect("no NUL")
+            };
+
+            let mount_points = fs::read_to_string("/proc/self/mountinfo")?
+                .lines()
+                .flat_map(|line| line.split(' ').nth(4))
+                .map(unescape)
+                .map(|p| c_string(OsStr::from_bytes(&p)))
+                .collect::<io::Result<_>>()?;
+
+            Ok(Self {
+                uid_map: map(uid),
+                gid_map: map(gid),
+                mount_points,
+                options,
+            })
+        }
+
+        /// Runs in the child process between `fork` and `exec`.
+        pub(super) fn enter(&self, scratch: &CStr) -> io::Result<()> {
+            unsafe {
+                check(libc::unshare(
+                    libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWNET,
+                ))?;
+
+                write_file(b"/proc/self/setgroups\0", b"deny")?;
+                write_file(b"/proc/self/uid_map\0", self.uid_map.as_bytes())?;
+                write_file(b"/proc/self/gid_map\0", self.gid_map.as_bytes())?;
+
+                // Do not propagate anything to the original namespace.
+                check(libc::mount(
+                    ptr::null(),
+                    b"/\0".as_ptr() as _,
+                    ptr::null(),
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    ptr::null(),
+                ))?;
+
+                // A bind mount of its own, so that it stays writable.
+                check(libc::mount(
+                    scratch.as_ptr(),
+                    scratch.as_ptr(),
+                    ptr::null(),
+                    libc::MS_BIND | libc::MS_REC,
+                    ptr::null(),
+                ))?;
+
+                for mount_point in &self.mount_points {
+                    remount_read_only(mount_point)?;
+                }
+
+                // The working directory still refers to the original mount. Look it up again.
+                let mut cwd = [0; libc::PATH_MAX as usize];
+                if libc::getcwd(cwd.as_mut_ptr(), cwd.len()).is_null() {
+                    return Err(io::Error::last_os_error());
+                }
+                check(libc::chdir(cwd.as_ptr()))?;
+
+                let SandboxOptions {
+                    address_space,
+                    processes,
+                    file_size,
+                } = self.options;
+
+                for &(resource, limit) in &[
+                    (libc::RLIMIT_AS, address_space),
+                    (libc::RLIMIT_NPROC, processes),
+                    (libc::RLIMIT_FSIZE, file_size),
+                ] {
+                    if let Some(limit) = limit {
+                        let limit = libc::rlimit {
+                            rlim_cur: limit as _,
+                            rlim_max: limit as _,
+                        };
+                        check(libc::setrlimit(resource, &limit))?;
+                    }
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    unsafe fn remount_read_only(mount_point: &CStr) -> io::Result<()> {
+        let mut stat = mem::MaybeUninit::<libc::statvfs>::zeroed();
+
+        if libc::statvfs(mount_point.as_ptr(), stat.as_mut_ptr()) == -1 {
+            let err = io::Error::last_os_error();
+            // Hidden under another mount or not reachable. It is not reachable from the child either.
+            return match err.raw_os_error() {
+                Some(libc::ENOENT) | Some(libc::EACCES) | Some(libc::ENOTDIR) => Ok(()),
+                _ => Err(err),
+            };
+        }
+
+        // Flags locked by the original namespace must be kept.
+        let f_flag = stat.assume_init().f_flag;
+        let mut flags = libc::MS_REMOUNT | libc::MS_BIND | libc::MS_RDONLY;
+        for &(st, ms) in &[
+            (libc::ST_NOSUID, libc::MS_NOSUID),
+            (libc::ST_NODEV, libc::MS_NODEV),
+            (libc::ST_NOEXEC, libc::MS_NOEXEC),
+            (libc::ST_NOATIME, libc::MS_NOATIME),
+            (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+            (libc::ST_RELATIME, libc::MS_RELATIME),
+        ] {
+            if f_flag & st != 0 {
+                flags |= ms;
+            }
+        }
+
+        check(libc::mount(
+            ptr::null(),
+            mount_point.as_ptr(),
+            ptr::null(),
+            flags,
+            ptr::null(),
+        ))
+    }
+
+    unsafe fn write_file(path: &[u8], content: &[u8]) -> io::Result<()> {
+        let fd = libc::open(path.as_ptr() as _, libc::O_WRONLY | libc::O_CLOEXEC);
+        check(fd)?;
+        let written = libc::write(fd, content.as_ptr() as _, content.len());
+        libc::close(fd);
+        if written != content.len() as isize {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn check(ret: libc::c_int) -> io::Result<()> {
+        if ret == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(super) fn c_string(s: &OsStr) -> io::Result<CString> {
+        CString::new(s.as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    /// Decodes octal escapes such as `\040` in `/proc/self/mountinfo`.
+    fn unescape(s: &str) -> Vec<u8> {
+        let s = s.as_bytes();
+        let mut acc = Vec::with_capacity(s.len());
+        let mut i = 0;
+        while i < s.len() {
+            match s.get(i..i + 4) {
+                Some([b'\\', digits @ ..]) if digits.iter().all(|d| (b'0'..=b'7').contains(d)) => {
+                    acc.push(digits.iter().fold(0, |n, d| n * 8 + u16::from(d - b'0')) as u8);
+                    i += 4;
+                }
+                _ => {
+                    acc.push(s[i]);
+                    i += 1;
+                }
+            }
+        }
+        acc
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::{
+            judge::{CommandExpression, JudgeOptions, ResourceLimits, SandboxOptions, Verdict},
+            testsuite::{BatchTestCase, ExpectedOutput, TestCase},
+        };
+        use indicatif::ProgressDrawTarget;
+        use maplit::btreemap;
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        #[test]
+        fn unescape() {
+            assert_eq!(
+                b"/mnt/a b\\x".to_vec(),
+                super::unescape(r"/mnt/a\040b\134x")
+            );
+        }
+
+        #[test]
+        fn read_only_outside_scratch() {
+            let outside = tempfile::Builder::new()
+                .prefix("snowchains-sandbox-test")
+                .tempdir()
+                .unwrap();
+
+            let cmd = CommandExpression {
+                program: "sh".into(),
+                args: vec![
+                    "-c".into(),
+                    r#"echo > "$TMPDIR/ok" || exit 100; echo > "$1/ng""#.into(),
+                    "sh".into(),
+                    outside.path().into(),
+                ],
+                cwd: outside.path().to_owned(),
+                env: btreemap!(),
+                resource_limits: ResourceLimits::default(),
+            };
+
+            let test_case = TestCase::Batch(BatchTestCase {
+                name: None,
+                timelimit: Some(Duration::from_secs(10)),
+                memorylimit: None,
+                outputlimit: None,
+                inputfile: None,
+                outputfile: None,
+                input: "".into(),
+                output: ExpectedOutput::Pass,
+            });
+
+            let outcome = crate::judge::judge(
+                ProgressDrawTarget::hidden(),
+                futures_util::future::pending,
+                &cmd,
+                &[test_case],
+                JudgeOptions {
+                    sandbox: Some(SandboxOptions::default()),
+                    ..JudgeOptions::default()
+                },
+            )
+            .unwrap();
+
+            // Only the write outside `$TMPDIR` fails.
+            assert!(matches!(
+                outcome.verdicts[0],
+                Verdict::RuntimeError { status, ref stderr, .. }
+                    if status.code() != Some(100) && stderr.contains("Read-only file system")
+            ));
+            assert!(!outside.path().join("ng").exists());
+        }
+    }
+}
@@ -0,0 +1,410 @@
+use super::{judge, CommandExpression, JudgeOptions, Verdict};
+use crate::testsuite::{BatchTestCase, ExpectedOutput, TestCase};
+use anyhow::{anyhow, bail, Context as _};
+use futures_util::future;
+use indicatif::ProgressDrawTarget;
+use std::{cmp, future::Future, process::Stdio, sync::Arc, time::Duration};
+use tokio::io::AsyncWriteExt as _;
+
+#[derive(Debug, Clone)]
+pub struct ShrinkOutcome {
+    pub test_case: BatchTestCase,
+    pub verdict: Verdict,
+    pub steps: usize,
+}
+
+/// Reduces the input of `test_case` while `solution` keeps failing with the same kind of verdict.
+///
+/// The input is reduced with `shrinker` if any, then by removing lines, then by removing tokens.
+/// `shrinker` receives the current input from stdin and prints a smaller one. It is run again
+/// while its outputs keep failing.
+///
+/// If `reference` is given, the expected output of each candidate is replaced with the output of
+/// `reference`, and candidates on which `reference` fails are discarded.
+pub fn shrink<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
+    ctrl_c: fn() -> C,
+    solution: &CommandExpression,
+    reference: Option<&CommandExpression>,
+    shrinker: Option<&CommandExpression>,
+    test_case: &BatchTestCase,
+    options: JudgeOptions,
+    mut on_step: impl FnMut(&BatchTestCase),
+) -> anyhow::Result<ShrinkOutcome> {
+    let options = JudgeOptions {
+        fail_fast: false,
+        ..options
+    };
+
+    let verdict = judge_one(ctrl_c, solution, test_case, options)?;
+
+    if matches!(verdict, Verdict::Accepted { .. } | Verdict::Skipped { .. }) {
+        bail!("The test case did not fail: {}", verdict.summary());
+    }
+    if let (Verdict::WrongAnswer { .. }, None, Some(_)) =
+        (&verdict, reference, test_case.output.text())
+    {
+        bail!("Shrinking `Wrong Answer` requires a reference solution");
+    }
+
+    let mut state = Shrinker {
+        ctrl_c,
+        solution,
+        reference,
+        options,
+        kind: verdict.kind(),
+        current: (test_case.clone(), verdict),
+        steps: 0,
+    };
+
+    loop {
+        let steps = state.steps;
+
+        if let Some(shrinker) = shrinker {
+            while let Some(candidate) = run_shrinker(shrinker, &state.current.0.input)? {
+                if !state.try_candidates(vec![candidate], &mut on_step)? {
+                    break;
+                }
+            }
+        }
+
+        state.remove_units(lines, &mut on_step)?;
+        state.remove_units(tokens, &mut on_step)?;
+
+        if state.steps == steps {
+            break;
+        }
+    }
+
+    let Shrinker {
+        current: (test_case, verdict),
+        steps,
+        ..
+    } = state;
+
+    Ok(ShrinkOutcome {
+        test_case,
+        verdict,
+        steps,
+    })
+}
+
+struct Shrinker<'a, C> {
+    ctrl_c: fn() -> C,
+    solution: &'a CommandExpression,
+    reference: Option<&'a CommandExpression>,
+    options: JudgeOptions,
+    kind: &'static str,
+    current: (BatchTestCase, Verdict),
+    steps: usize,
+}
+
+impl<C: 'static + Future<Output = tokio::io::Result<()>> + Send> Shrinker<'_, C> {
+    /// Removes chunks of units in the way of delta debugging, halving the chunk size when no chunk
+    /// can be removed.
+    fn remove_units(
+        &mut self,
+        split: fn(&str) -> Vec<Unit<'_>>,
+        on_step: &mut impl FnMut(&BatchTestCase),
+    ) -> anyhow::Result<()> {
+        let mut chunk = usize::MAX;
+
+        loop {
+            let input = self.current.0.input.clone();
+            let units = split(&input);
+            let removable = (0..units.len())
+                .filter(|&i| units[i].removable)
+                .collect::<Vec<_>>();
+
+            if removable.is_empty() {
+                return Ok(());
+            }
+
+            chunk = cmp::max(cmp::min(chunk, removable.len() / 2), 1);
+
+            let candidates = removable
+                .chunks(chunk)
+                .map(|removed| {
+                    units
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| removed.binary_search(i).is_err())
+                        .map(|(_, unit)| unit.text)
+                        .collect::<String>()
+                })
+                .collect();
+
+            if !self.try_candidates(candidates, on_step)? {
+                if chunk == 1 {
+                    return Ok(());
+                }
+                chunk /= 2;
+            }
+        }
+    }
+
+    /// Tests `candidates` in order, and adopts the first one that fails in the same way.
+    fn try_candidates(
+        &mut self,
+        candidates: Vec<String>,
+        on_step: &mut impl FnMut(&BatchTestCase),
+    ) -> anyhow::Result<bool> {
+        let jobs = self.options.jobs.map_or_else(num_cpus::get, |n| n.get());
+
+        for candidates in candidates.chunks(jobs) {
+            let mut test_cases = candidates
+                .iter()
+                .map(|input| BatchTestCase {
+                    input: input.as_str().into(),
+                    ..self.current.0.clone()
+                })
+                .collect::<Vec<_>>();
+
+            if let Some(reference) = self.reference {
+                let outcome = judge(
+                    ProgressDrawTarget::hidden(),
+                    self.ctrl_c,
+                    reference,
+                    &test_cases
+                        .iter()
+                        .map(|test_case| {
+                            TestCase::Batch(BatchTestCase {
+                                timelimit: None,
+                                memorylimit: None,
+                                outputlimit: None,
+                                output: ExpectedOutput::Pass,
+                                ..test_case.clone()
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                    JudgeOptions {
+                        timelimit_on_cpu_time: false,
+                        output_limit: None,
+                        ..self.options
+                    },
+                )?;
+
+                test_cases = test_cases
+                    .into_iter()
+                    .zip(outcome.verdicts)
+                    .flat_map(|(test_case, verdict)| match verdict {
+                        Verdict::Accepted { stdout, .. } => Some(BatchTestCase {
+                            output: test_case.output.with_text(stdout),
+                            ..test_case
+                        }),
+                        _ => None,
+                    })
+                    .collect();
+
+                if test_cases.is_empty() {
+                    continue;
+                }
+            }
+
+            let outcome = judge(
+                ProgressDrawTarget::hidden(),
+                self.ctrl_c,
+                self.solution,
+                &test_cases
+                    .iter()
+                    .cloned()
+                    .map(TestCase::Batch)
+                    .collect::<Vec<_>>(),
+                self.options,
+            )?;
+
+            let found = test_cases
+                .into_iter()
+                .zip(outcome.verdicts)
+                .find(|(_, verdict)| verdict.kind() == self.kind);
+
+            if let Some(found) = found {
+                self.current = found;
+                self.steps += 1;
+                on_step(&self.current.0);
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+struct Unit<'a> {
+    text: &'a str,
+    removable: bool,
+}
+
+fn lines(input: &str) -> Vec<Unit<'_>> {
+    input
+        .split_inclusive('\n')
+        .map(|text| Unit {
+            text,
+            removable: true,
+        })
+        .collect()
+}
+
+/// Splits `input` into tokens with their trailing spaces. Line breaks are kept.
+fn tokens(input: &str) -> Vec<Unit<'_>> {
+    let mut units = vec![];
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        let (len, removable) = if rest.starts_with(|c| c == '\n' || c == '\r') {
+            (1, false)
+        } else {
+            let token = rest.find(char::is_whitespace).unwrap_or_else(|| rest.len());
+            let spaces = rest[token..]
+                .find(|c: char| !c.is_whitespace() || c == '\n' || c == '\r')
+                .unwrap_or_else(|| rest.len() - token);
+            (token + spaces, token > 0)
+        };
+        units.push(Unit {
+            text: &rest[..len],
+            removable,
+        });
+        rest = &rest[len..];
+    }
+
+    units
+}
+
+fn judge_one<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
+    ctrl_c: fn() -> C,
+    cmd: &CommandExpression,
+    test_case: &BatchTestCase,
+    options: JudgeOptions,
+) -> anyhow::Result<Verdict> {
+    let outcome = judge(
+        ProgressDrawTarget::hidden(),
+        ctrl_c,
+        cmd,
+        &[test_case.clone().into()],
+        options,
+    )?;
+    Ok(outcome.verdicts.into_iter().next().expect("one test case"))
+}
+
+/// Runs `shrinker` with `input`. Returns `None` unless it succeeds with a smaller input.
+fn run_shrinker(shrinker: &CommandExpression, input: &Arc<str>) -> anyhow::Result<Option<String>> {
+    // Same as the checker.
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    let mut rt = tokio::runtime::Builder::new()
+        .enable_io()
+        .enable_time()
+        .basic_scheduler()
+        .build()?;
+
+    let output = rt.block_on(async {
+        let mut child = tokio::process::Command::new(&shrinker.program)
+            .args(&shrinker.args)
+            .current_dir(&shrinker.cwd)
+            .envs(&shrinker.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Could not execute {:?}", shrinker.program))?;
+
+        // Write concurrently so that neither side blocks on a full pipe. The shrinker may exit
+        // without reading all of the input.
+        let mut stdin = child.stdin.take().expect("specified `Stdio::piped()`");
+        let write = async move {
+            let _ = stdin.write_all(input.as_bytes()).await;
+        };
+
+        let (_, output) =
+            tokio::time::timeout(TIMEOUT, future::join(write, child.wait_with_output()))
+                .await
+                .map_err(|_| anyhow!("The shrinker timed out after {} s", TIMEOUT.as_secs()))?;
+
+        Ok::<_, anyhow::Error>(output?)
+    })?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(String::from_utf8(output.stdout)
+        .ok()
+        .filter(|candidate| candidate.len() < input.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{CommandExpression, JudgeOptions, ResourceLimits, Verdict};
+    use crate::testsuite::{BatchTestCase, ExpectedOutput};
+    use maplit::btreemap;
+    use pretty_assertions::assert_eq;
+    use std::env;
+
+    fn sh(script: &str) -> CommandExpression {
+        CommandExpression {
+            program: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            cwd: env::temp_dir(),
+            env: btreemap!(),
+            resource_limits: ResourceLimits::default(),
+        }
+    }
+
+    #[test]
+    fn shrink() {
+        // Fails on any input containing the token `7`.
+        let solution = sh("! grep -qw 7");
+
+        let test_case = BatchTestCase {
+            name: None,
+            timelimit: None,
+            memorylimit: None,
+            outputlimit: None,
+            inputfile: None,
+            outputfile: None,
+            input: "3\n1 2 3\n4 7 5\n6\n".into(),
+            output: ExpectedOutput::Pass,
+        };
+
+        let mut steps = vec![];
+
+        let outcome = super::shrink(
+            futures_util::future::pending,
+            &solution,
+            None,
+            Some(&sh("sed 1d")),
+            &test_case,
+            JudgeOptions::default(),
+            |test_case| steps.push(test_case.input.clone()),
+        )
+        .unwrap();
+
+        assert_eq!("7 \n", &*outcome.test_case.input);
+        assert!(matches!(outcome.verdict, Verdict::RuntimeError { .. }));
+        assert_eq!(steps.len(), outcome.steps);
+        assert_eq!("1 2 3\n4 7 5\n6\n", &*steps[0]);
+    }
+
+    #[test]
+    fn tokens() {
+        let units = super::tokens("3\n1  2\t3\n\n x\n");
+        assert_eq!(
+            vec![
+                ("3", true),
+                ("\n", false),
+                ("1  ", true),
+                ("2\t", true),
+                ("3", true),
+                ("\n", false),
+                ("\n", false),
+                (" ", false),
+                ("x", true),
+                ("\n", false),
+            ],
+            units
+                .iter()
+                .map(|u| (u.text, u.removable))
+                .collect::<Vec<_>>(),
+        );
+    }
+}
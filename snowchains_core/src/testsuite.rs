@@ -1,15 +1,18 @@
+use crate::judge::{CommandExpression, ResourceLimits};
 use anyhow::{bail, ensure, Context as _};
+use human_size::{Byte, Size};
 use humantime_serde::Serde;
 use ignore::{overrides::OverrideBuilder, WalkBuilder};
 use itertools::{EitherOrBoth, Itertools as _};
 use maplit::hashmap;
+use regex::Regex;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use std::{
     borrow::Borrow,
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs,
     hash::Hash,
-    path::Path,
+    path::{Component, Path},
     str::FromStr,
     sync::Arc,
     time::Duration,
@@ -31,7 +34,19 @@ impl TestSuite {
 
                 yaml += &key_value("type", "Batch").ok()?;
                 yaml += &key_value("timelimit", Serde::from(suite.timelimit)).ok()?;
-                yaml += &key_value("match", suite.r#match).ok()?;
+                if let Some(memorylimit) = suite.memorylimit {
+                    yaml += &key_value("memorylimit", memorylimit).ok()?;
+                }
+                if let Some(outputlimit) = suite.outputlimit {
+                    yaml += &key_value("outputlimit", outputlimit).ok()?;
+                }
+                if let Some(inputfile) = &suite.inputfile {
+                    yaml += &key_value("inputfile", inputfile).ok()?;
+                }
+                if let Some(outputfile) = &suite.outputfile {
+                    yaml += &key_value("outputfile", outputfile).ok()?;
+                }
+                yaml += &key_value("match", &suite.r#match).ok()?;
 
                 yaml += if suite.cases.is_empty() {
                     "\ncases: []\n"
@@ -48,15 +63,24 @@ impl TestSuite {
 
                     part += &key_value_in_literal_style("in", &case.r#in).ok()?;
 
-                    if let Some(out) = &case.out {
-                        part += &key_value_in_literal_style("out", out).ok()?;
+                    match &*case.out {
+                        [] => {}
+                        [out] => part += &key_value_in_literal_style("out", out).ok()?,
+                        outs => {
+                            part += &key_value("out", outs.iter().map(|s| &**s).collect::<Vec<_>>())
+                                .ok()?
+                        }
                     }
 
                     if let Some(timelimit) = case.timelimit {
                         part += &key_value("timelimit", Serde::from(timelimit)).ok()?;
                     }
 
-                    if let Some(r#match) = case.r#match {
+                    if let Some(memorylimit) = case.memorylimit {
+                        part += &key_value("memorylimit", memorylimit).ok()?;
+                    }
+
+                    if let Some(r#match) = &case.r#match {
                         part += &key_value("match", r#match).ok()?;
                     }
 
@@ -151,6 +175,17 @@ impl TestSuite {
 pub struct BatchTestSuite {
     #[serde(default, with = "humantime_serde")]
     pub timelimit: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memorylimit: Option<Size>,
+    /// Limit for each of stdout and stderr.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outputlimit: Option<Size>,
+    /// File the solution reads the input from, instead of stdin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputfile: Option<String>,
+    /// File the solution writes the output to, instead of stdout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outputfile: Option<String>,
     pub r#match: Match,
     #[serde(default)]
     pub cases: Vec<PartialBatchTestCase>,
@@ -162,34 +197,51 @@ impl BatchTestSuite {
     pub fn load_test_cases<S: Borrow<str> + Eq + Hash>(
         &self,
         parent_dir: &Path,
-        mut names: Option<HashSet<S>>,
+        names: Option<HashSet<S>>,
     ) -> anyhow::Result<Vec<BatchTestCase>> {
+        self.validate()?;
+
         let mut cases = self.cases.clone();
         for extend in &self.extend {
             cases.extend(extend.load_test_cases(parent_dir)?);
         }
 
-        let cases = cases
+        filter_by_names(cases, names, |case| case.name.as_deref())?
             .into_iter()
-            .filter(
-                |PartialBatchTestCase { name, .. }| match (names.as_mut(), name.as_ref()) {
-                    (Some(names), Some(name)) => names.remove(name),
-                    _ => true,
-                },
-            )
-            .map(|case| BatchTestCase::new(case, self.timelimit, self.r#match))
-            .collect();
-
-        if let Some(names) = names {
-            if !names.is_empty() {
-                bail!(
-                    "No such test cases: {:?}",
-                    names.iter().map(Borrow::borrow).collect::<BTreeSet<_>>(),
+            .map(|case| {
+                let case = BatchTestCase::new(case, self, parent_dir);
+                case.output.validate().with_context(|| {
+                    format!(
+                        "Invalid test case: {:?}",
+                        case.name.as_deref().unwrap_or("")
+                    )
+                })?;
+                Ok(case)
+            })
+            .collect()
+    }
+
+    /// Checks that `inputfile` and `outputfile` are plain file names, which are created in the
+    /// directory the solution runs in.
+    fn validate(&self) -> anyhow::Result<()> {
+        for (key, name) in &[
+            ("inputfile", &self.inputfile),
+            ("outputfile", &self.outputfile),
+        ] {
+            if let Some(name) = name {
+                let mut components = Path::new(name).components();
+                ensure!(
+                    matches!(
+                        (components.next(), components.next()),
+                        (Some(Component::Normal(_)), None)
+                    ),
+                    "`{}` must be a file name without directories: {:?}",
+                    key,
+                    name,
                 );
             }
         }
-
-        Ok(cases)
+        Ok(())
     }
 }
 
@@ -198,10 +250,13 @@ pub struct PartialBatchTestCase {
     pub name: Option<String>,
     #[serde(with = "serde_fn::arc_str")]
     pub r#in: Arc<str>,
-    #[serde(with = "serde_fn::option_arc_str")]
-    pub out: Option<Arc<str>>,
+    /// Acceptable outputs. Any output is accepted if empty.
+    #[serde(default, with = "serde_fn::arc_strs")]
+    pub out: Vec<Arc<str>>,
     #[serde(default, with = "humantime_serde")]
     pub timelimit: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memorylimit: Option<Size>,
     pub r#match: Option<Match>,
 }
 
@@ -293,9 +348,10 @@ impl Additional {
                         Ok(PartialBatchTestCase {
                             name: Some(name),
                             r#in,
-                            out,
+                            out: out.into_iter().collect(),
                             timelimit: *timelimit,
-                            r#match: *r#match,
+                            memorylimit: None,
+                            r#match: r#match.clone(),
                         })
                     })
                     .collect()
@@ -304,41 +360,186 @@ impl Additional {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum Match {
     Exact,
     Lines,
+    /// Compares whitespace-separated tokens, ignoring how they are separated.
+    Tokens,
+    /// Compares lines ignoring case.
+    CaseInsensitive,
+    /// Compares lines as a multiset, for problems that accept answers in any order.
+    UnorderedLines,
+    /// Treats each expected line as a regular expression that must match the whole actual line.
+    Regex,
     Float {
         relative_error: Option<PositiveFinite<f64>>,
         absolute_error: Option<PositiveFinite<f64>>,
     },
+    /// Runs `command` with the input, the actual output, and the expected output as testlib does.
+    Checker {
+        command: Vec<String>,
+    },
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
 pub struct InteractiveTestSuite {
     #[serde(default, with = "humantime_serde")]
     pub timelimit: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interactor: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cases: Vec<PartialInteractiveTestCase>,
+}
+
+impl InteractiveTestSuite {
+    pub fn load_test_cases<S: Borrow<str> + Eq + Hash>(
+        &self,
+        parent_dir: &Path,
+        names: Option<HashSet<S>>,
+    ) -> anyhow::Result<Vec<InteractiveTestCase>> {
+        let interactor = match self.interactor.as_deref() {
+            Some([program, args @ ..]) => CommandExpression {
+                program: program.into(),
+                args: args.iter().map(Into::into).collect(),
+                cwd: parent_dir.to_owned(),
+                env: BTreeMap::new(),
+                resource_limits: ResourceLimits::default(),
+            },
+            _ => bail!("`interactor` is not specified"),
+        };
+
+        filter_by_names(self.cases.clone(), names, |case| case.name.as_deref()).map(|cases| {
+            cases
+                .into_iter()
+                .map(|case| InteractiveTestCase {
+                    name: case.name,
+                    timelimit: case.timelimit.or(self.timelimit),
+                    input: case.r#in,
+                    interactor: interactor.clone(),
+                })
+                .collect()
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct PartialInteractiveTestCase {
+    pub name: Option<String>,
+    #[serde(with = "serde_fn::arc_str")]
+    pub r#in: Arc<str>,
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timelimit: Option<Duration>,
+}
+
+fn filter_by_names<T, S: Borrow<str> + Eq + Hash>(
+    cases: Vec<T>,
+    mut names: Option<HashSet<S>>,
+    name: impl Fn(&T) -> Option<&str>,
+) -> anyhow::Result<Vec<T>> {
+    let cases = cases
+        .into_iter()
+        .filter(|case| match (names.as_mut(), name(case)) {
+            (Some(names), Some(name)) => names.remove(name),
+            _ => true,
+        })
+        .collect();
+
+    if let Some(names) = names {
+        if !names.is_empty() {
+            bail!(
+                "No such test cases: {:?}",
+                names.iter().map(Borrow::borrow).collect::<BTreeSet<_>>(),
+            );
+        }
+    }
+
+    Ok(cases)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TestCase {
+    Batch(BatchTestCase),
+    Interactive(InteractiveTestCase),
+}
+
+impl TestCase {
+    pub(crate) fn name(&self) -> Option<&str> {
+        match self {
+            Self::Batch(BatchTestCase { name, .. })
+            | Self::Interactive(InteractiveTestCase { name, .. }) => name.as_deref(),
+        }
+    }
+
+    pub(crate) fn timelimit(&self) -> Option<Duration> {
+        match *self {
+            Self::Batch(BatchTestCase { timelimit, .. })
+            | Self::Interactive(InteractiveTestCase { timelimit, .. }) => timelimit,
+        }
+    }
+}
+
+impl From<BatchTestCase> for TestCase {
+    fn from(test_case: BatchTestCase) -> Self {
+        Self::Batch(test_case)
+    }
+}
+
+impl From<InteractiveTestCase> for TestCase {
+    fn from(test_case: InteractiveTestCase) -> Self {
+        Self::Interactive(test_case)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct BatchTestCase {
     pub name: Option<String>,
     pub timelimit: Option<Duration>,
+    pub memorylimit: Option<u64>,
+    pub outputlimit: Option<u64>,
+    pub inputfile: Option<String>,
+    pub outputfile: Option<String>,
     pub input: Arc<str>,
     pub output: ExpectedOutput,
 }
 
 impl BatchTestCase {
-    fn new(case: PartialBatchTestCase, timelimit: Option<Duration>, matching: Match) -> Self {
+    /// Completes `case` with the defaults in `suite`.
+    pub fn new(case: PartialBatchTestCase, suite: &BatchTestSuite, parent_dir: &Path) -> Self {
         BatchTestCase {
             name: case.name,
-            timelimit: case.timelimit.or(timelimit),
+            timelimit: case.timelimit.or(suite.timelimit),
+            memorylimit: case
+                .memorylimit
+                .or(suite.memorylimit)
+                .map(|size| size.into::<Byte>().value() as u64),
+            outputlimit: suite
+                .outputlimit
+                .map(|size| size.into::<Byte>().value() as u64),
+            inputfile: suite.inputfile.clone(),
+            outputfile: suite.outputfile.clone(),
             input: case.r#in,
-            output: ExpectedOutput::new(case.out, case.r#match.unwrap_or(matching)),
+            output: ExpectedOutput::new(
+                case.out,
+                case.r#match.as_ref().unwrap_or(&suite.r#match),
+                parent_dir,
+            ),
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct InteractiveTestCase {
+    pub name: Option<String>,
+    pub timelimit: Option<Duration>,
+    pub input: Arc<str>,
+    pub interactor: CommandExpression,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExpectedOutput {
     Pass,
@@ -348,22 +549,75 @@ pub enum ExpectedOutput {
     Lines {
         text: Arc<str>,
     },
+    Tokens {
+        text: Arc<str>,
+    },
+    CaseInsensitive {
+        text: Arc<str>,
+    },
+    UnorderedLines {
+        text: Arc<str>,
+    },
+    Regex {
+        text: Arc<str>,
+        patterns: LineRegexes,
+    },
     Float {
         text: Arc<str>,
         relative_error: Option<PositiveFinite<f64>>,
         absolute_error: Option<PositiveFinite<f64>>,
     },
+    Checker {
+        text: Option<Arc<str>>,
+        command: CommandExpression,
+    },
+    /// Accepts outputs accepted by any of `alternatives`.
+    AnyOf {
+        alternatives: Vec<ExpectedOutput>,
+    },
 }
 
 impl ExpectedOutput {
-    fn new(text: Option<Arc<str>>, matching: Match) -> Self {
+    fn new(mut texts: Vec<Arc<str>>, matching: &Match, parent_dir: &Path) -> Self {
+        if texts.len() > 1 {
+            return Self::AnyOf {
+                alternatives: texts
+                    .into_iter()
+                    .map(|text| Self::new(vec![text], matching, parent_dir))
+                    .collect(),
+            };
+        }
+
+        let text = if texts.is_empty() {
+            None
+        } else {
+            Some(texts.swap_remove(0))
+        };
+
         match (text, matching) {
+            (text, Match::Checker { command }) => Self::Checker {
+                text,
+                command: CommandExpression {
+                    program: command.get(0).cloned().unwrap_or_default().into(),
+                    args: command.iter().skip(1).map(Into::into).collect(),
+                    cwd: parent_dir.to_owned(),
+                    env: BTreeMap::new(),
+                    resource_limits: ResourceLimits::default(),
+                },
+            },
             (None, _) => Self::Pass,
             (Some(text), Match::Exact) => Self::Exact { text },
             (Some(text), Match::Lines) => Self::Lines { text },
+            (Some(text), Match::Tokens) => Self::Tokens { text },
+            (Some(text), Match::CaseInsensitive) => Self::CaseInsensitive { text },
+            (Some(text), Match::UnorderedLines) => Self::UnorderedLines { text },
+            (Some(text), Match::Regex) => Self::Regex {
+                patterns: LineRegexes::new(&text),
+                text,
+            },
             (
                 Some(text),
-                Match::Float {
+                &Match::Float {
                     relative_error,
                     absolute_error,
                 },
@@ -375,53 +629,192 @@ impl ExpectedOutput {
         }
     }
 
+    /// Replaces the expected text, keeping the way to compare.
+    pub(crate) fn with_text(&self, new_text: Arc<str>) -> Self {
+        match self {
+            Self::Pass => Self::Pass,
+            Self::Exact { .. } => Self::Exact { text: new_text },
+            Self::Lines { .. } => Self::Lines { text: new_text },
+            Self::Tokens { .. } => Self::Tokens { text: new_text },
+            Self::CaseInsensitive { .. } => Self::CaseInsensitive { text: new_text },
+            Self::UnorderedLines { .. } => Self::UnorderedLines { text: new_text },
+            Self::Regex { .. } => Self::Regex {
+                patterns: LineRegexes::new(&new_text),
+                text: new_text,
+            },
+            Self::Float {
+                relative_error,
+                absolute_error,
+                ..
+            } => Self::Float {
+                text: new_text,
+                relative_error: *relative_error,
+                absolute_error: *absolute_error,
+            },
+            Self::Checker { command, .. } => Self::Checker {
+                text: Some(new_text),
+                command: command.clone(),
+            },
+            Self::AnyOf { alternatives } => alternatives[0].with_text(new_text),
+        }
+    }
+
     pub(crate) fn is_float(&self) -> bool {
-        matches!(self, Self::Float { .. })
+        match self {
+            Self::Float { .. } => true,
+            Self::AnyOf { alternatives } => alternatives[0].is_float(),
+            _ => false,
+        }
+    }
+
+    /// The alternatives of `AnyOf`. Empty for the others.
+    pub(crate) fn alternatives(&self) -> &[Self] {
+        match self {
+            Self::AnyOf { alternatives } => alternatives,
+            _ => &[],
+        }
     }
 
-    pub(crate) fn text(&self) -> Option<&Arc<str>> {
+    pub fn text(&self) -> Option<&Arc<str>> {
         match self {
-            Self::Exact { text } | Self::Lines { text } | Self::Float { text, .. } => Some(text),
+            Self::Exact { text }
+            | Self::Lines { text }
+            | Self::Tokens { text }
+            | Self::CaseInsensitive { text }
+            | Self::UnorderedLines { text }
+            | Self::Regex { text, .. }
+            | Self::Float { text, .. } => Some(text),
+            Self::Checker { text, .. } => text.as_ref(),
+            Self::AnyOf { alternatives } => alternatives[0].text(),
             Self::Pass => None,
         }
     }
 
+    /// Compares `actual` with the expected text.
+    ///
+    /// `Checker` cannot be decided here. `judge` runs the command instead.
     pub(crate) fn accepts(&self, actual: &str) -> bool {
         match self {
             Self::Pass => true,
+            Self::Checker { .. } => unreachable!("checkers should be run by `judge`"),
+            Self::AnyOf { alternatives } => alternatives.iter().any(|a| a.accepts(actual)),
             Self::Exact { text } => &**text == actual,
             Self::Lines { text } => text.lines().eq(actual.lines()),
-            Self::Float {
-                text,
-                relative_error,
-                absolute_error,
-            } => {
-                let (text, actual) = (text.lines(), actual.lines());
-                let relative_error = relative_error.map(PositiveFinite::get).unwrap_or(0.0);
-                let absolute_error = absolute_error.map(PositiveFinite::get).unwrap_or(0.0);
-
-                text.zip_longest(actual).all(|zip| {
-                    if let EitherOrBoth::Both(line1, line2) = zip {
-                        let (words1, words2) = (line1.split_whitespace(), line2.split_whitespace());
-                        words1.zip_longest(words2).all(|zip| match zip {
-                            EitherOrBoth::Both(s1, s2) => {
-                                match (s1.parse::<f64>(), s2.parse::<f64>()) {
-                                    (Ok(v1), Ok(v2)) => {
-                                        (v1 - v2).abs() <= absolute_error
-                                            || ((v1 - v2) / v2).abs() <= relative_error
-                                    }
-                                    _ => s1 == s2,
-                                }
-                            }
-                            EitherOrBoth::Left(_) | EitherOrBoth::Right(_) => false,
-                        })
-                    } else {
-                        false
+            Self::Tokens { text } => text.split_whitespace().eq(actual.split_whitespace()),
+            Self::UnorderedLines { text } => self.split_lines(text) == self.split_lines(actual),
+            Self::CaseInsensitive { text }
+            | Self::Regex { text, .. }
+            | Self::Float { text, .. } => text
+                .lines()
+                .zip_longest(actual.lines())
+                .all(|zip| matches!(zip, EitherOrBoth::Both(l1, l2) if self.line_matches(l1, l2))),
+        }
+    }
+
+    /// Checks that the expected text is valid for the way to compare.
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Regex { text, patterns } => {
+                for line in text.lines() {
+                    if let Some(Err(err)) = patterns.0.get(line) {
+                        return Err(err.clone().into());
+                    }
+                }
+            }
+            Self::AnyOf { alternatives } => {
+                for alternative in alternatives {
+                    if let Self::Checker { .. } = alternative {
+                        bail!(
+                            "`Checker` takes at most one `out`, but {} were given",
+                            alternatives.len(),
+                        );
                     }
+                    alternative.validate()?;
+                }
+            }
+            Self::Checker { command, .. } => {
+                if command.program.is_empty() {
+                    bail!("`command` of `Checker` is empty");
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Splits `text` into lines in the way `accepts` compares them.
+    ///
+    /// `Exact` keeps the last empty line so that a missing trailing newline is visible.
+    /// `UnorderedLines` sorts the lines.
+    pub(crate) fn split_lines<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        match self {
+            Self::Exact { .. } => text.split('\n').collect(),
+            Self::UnorderedLines { .. } => text.lines().sorted().collect(),
+            _ => text.lines().collect(),
+        }
+    }
+
+    pub(crate) fn line_matches(&self, expected: &str, actual: &str) -> bool {
+        match self {
+            Self::Tokens { .. } => expected.split_whitespace().eq(actual.split_whitespace()),
+            Self::Regex { patterns, .. } => match patterns.0.get(expected) {
+                Some(Ok(regex)) => regex.is_match(actual),
+                _ => false,
+            },
+            Self::CaseInsensitive { .. } => expected.to_lowercase() == actual.to_lowercase(),
+            Self::Float { .. } => {
+                let (words1, words2) = (expected.split_whitespace(), actual.split_whitespace());
+                words1.zip_longest(words2).all(|zip| match zip {
+                    EitherOrBoth::Both(s1, s2) => self.token_matches(s1, s2),
+                    EitherOrBoth::Left(_) | EitherOrBoth::Right(_) => false,
                 })
             }
+            _ => expected == actual,
         }
     }
+
+    pub(crate) fn token_matches(&self, expected: &str, actual: &str) -> bool {
+        if let Self::Float {
+            relative_error,
+            absolute_error,
+            ..
+        } = *self
+        {
+            let relative_error = relative_error.map(PositiveFinite::get).unwrap_or(0.0);
+            let absolute_error = absolute_error.map(PositiveFinite::get).unwrap_or(0.0);
+
+            if let (Ok(v1), Ok(v2)) = (expected.parse::<f64>(), actual.parse::<f64>()) {
+                return (v1 - v2).abs() <= absolute_error
+                    || ((v1 - v2) / v2).abs() <= relative_error;
+            }
+        }
+        if let Self::CaseInsensitive { .. } = self {
+            return expected.to_lowercase() == actual.to_lowercase();
+        }
+        expected == actual
+    }
+}
+
+/// The lines of the expected text of `ExpectedOutput::Regex`, each compiled once to match a whole
+/// line.
+#[derive(Debug, Clone)]
+pub struct LineRegexes(Arc<HashMap<String, Result<Regex, regex::Error>>>);
+
+impl LineRegexes {
+    pub fn new(text: &str) -> Self {
+        Self(Arc::new(
+            text.lines()
+                .map(|line| (line.to_owned(), Regex::new(&format!("^(?:{})$", line))))
+                .collect(),
+        ))
+    }
+}
+
+/// Compiled from the same lines.
+impl PartialEq for LineRegexes {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.keys().all(|line| other.0.contains_key(line))
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize)]
@@ -480,42 +873,55 @@ mod serde_fn {
         }
     }
 
-    pub(super) mod option_arc_str {
+    /// `null`, a string, or a sequence of strings.
+    pub(super) mod arc_strs {
         use serde::{Deserialize, Deserializer, Serializer};
         use std::sync::Arc;
 
-        pub(crate) fn serialize<S>(
-            this: &Option<Arc<str>>,
-            serializer: S,
-        ) -> Result<S::Ok, S::Error>
+        pub(crate) fn serialize<S>(this: &[Arc<str>], serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer,
         {
-            if let Some(s) = this {
-                serializer.serialize_some(&**s)
-            } else {
-                serializer.serialize_none()
+            match this {
+                [] => serializer.serialize_none(),
+                [s] => serializer.serialize_some(&**s),
+                ss => serializer.collect_seq(ss.iter().map(|s| &**s)),
             }
         }
 
-        pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<Arc<str>>, D::Error>
+        pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Arc<str>>, D::Error>
         where
             D: Deserializer<'de>,
         {
-            Option::<String>::deserialize(deserializer).map(|s| s.map(Into::into))
+            #[derive(Deserialize)]
+            #[serde(untagged)]
+            enum Repr {
+                One(String),
+                Many(Vec<String>),
+            }
+
+            Ok(match Option::<Repr>::deserialize(deserializer)? {
+                None => vec![],
+                Some(Repr::One(s)) => vec![s.into()],
+                Some(Repr::Many(ss)) => ss.into_iter().map(Into::into).collect(),
+            })
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::testsuite::{
-        Additional, BatchTestSuite, ExpectedOutput, Match, PartialBatchTestCase, PositiveFinite,
-        TestSuite,
+    use crate::{
+        judge::{CommandExpression, ResourceLimits},
+        testsuite::{
+            Additional, BatchTestSuite, ExpectedOutput, InteractiveTestCase, InteractiveTestSuite,
+            LineRegexes, Match, PartialBatchTestCase, PositiveFinite, TestSuite,
+        },
     };
     use difference::assert_diff;
+    use maplit::hashset;
     use pretty_assertions::assert_eq;
-    use std::time::Duration;
+    use std::{collections::BTreeMap, path::Path, time::Duration};
 
     #[test]
     fn atcoder_abc162_a() {
@@ -546,27 +952,34 @@ extend: []
 "#,
             &TestSuite::Batch(BatchTestSuite {
                 timelimit: Some(Duration::from_secs(2)),
+                memorylimit: None,
+                outputlimit: None,
+                inputfile: None,
+                outputfile: None,
                 r#match: Match::Lines,
                 cases: vec![
                     PartialBatchTestCase {
                         name: Some("Sample 1".to_owned()),
                         r#in: "117\n".into(),
-                        out: Some("Yes\n".into()),
+                        out: vec!["Yes\n".into()],
                         timelimit: None,
+                        memorylimit: None,
                         r#match: None,
                     },
                     PartialBatchTestCase {
                         name: Some("Sample 2".to_owned()),
                         r#in: "123\n".into(),
-                        out: Some("No\n".into()),
+                        out: vec!["No\n".into()],
                         timelimit: None,
+                        memorylimit: None,
                         r#match: None,
                     },
                     PartialBatchTestCase {
                         name: Some("Sample 3".to_owned()),
                         r#in: "777\n".into(),
-                        out: Some("Yes\n".into()),
+                        out: vec!["Yes\n".into()],
                         timelimit: None,
+                        memorylimit: None,
                         r#match: None,
                     },
                 ],
@@ -590,6 +1003,10 @@ extend:
 "#,
             &TestSuite::Batch(BatchTestSuite {
                 timelimit: Some(Duration::from_secs(2)),
+                memorylimit: None,
+                outputlimit: None,
+                inputfile: None,
+                outputfile: None,
                 r#match: Match::Lines,
                 cases: vec![],
                 extend: vec![Additional::Text {
@@ -630,6 +1047,10 @@ extend: []
 "#,
             &TestSuite::Batch(BatchTestSuite {
                 timelimit: Some(Duration::from_secs(2)),
+                memorylimit: None,
+                outputlimit: None,
+                inputfile: None,
+                outputfile: None,
                 r#match: Match::Float {
                     relative_error: Some(PositiveFinite(0.01)),
                     absolute_error: Some(PositiveFinite(0.01)),
@@ -638,15 +1059,17 @@ extend: []
                     PartialBatchTestCase {
                         name: Some("Sample 1".to_owned()),
                         r#in: "1\n".into(),
-                        out: Some("6.28318530717958623200\n".into()),
+                        out: vec!["6.28318530717958623200\n".into()],
                         timelimit: None,
+                        memorylimit: None,
                         r#match: None,
                     },
                     PartialBatchTestCase {
                         name: Some("Sample 2".to_owned()),
                         r#in: "73\n".into(),
-                        out: Some("458.67252742410977361942\n".into()),
+                        out: vec!["458.67252742410977361942\n".into()],
                         timelimit: None,
+                        memorylimit: None,
                         r#match: None,
                     },
                 ],
@@ -684,20 +1107,26 @@ extend: []
 "#,
             &TestSuite::Batch(BatchTestSuite {
                 timelimit: Some(Duration::from_secs(2)),
+                memorylimit: None,
+                outputlimit: None,
+                inputfile: None,
+                outputfile: None,
                 r#match: Match::Lines,
                 cases: vec![
                     PartialBatchTestCase {
                         name: Some("Sample 1".to_owned()),
                         r#in: "3\ncbaa\ndaacc\nacacac\n".into(),
-                        out: Some("aac\n".into()),
+                        out: vec!["aac\n".into()],
                         timelimit: None,
+                        memorylimit: None,
                         r#match: None,
                     },
                     PartialBatchTestCase {
                         name: Some("Sample 2".to_owned()),
                         r#in: "3\na\naa\nb\n".into(),
-                        out: Some("\n".into()),
+                        out: vec!["\n".into()],
                         timelimit: None,
+                        memorylimit: None,
                         r#match: None,
                     },
                 ],
@@ -706,6 +1135,288 @@ extend: []
         );
     }
 
+    #[test]
+    fn checker() {
+        test_serialize_deserialize(
+            r#"---
+type: Batch
+timelimit: 2s
+match:
+  Checker:
+    command:
+      - python3
+      - "./checker.py"
+
+cases:
+  - name: Sample 1
+    in: |
+      3
+    out: |
+      1 2 3
+  - name: Sample 2
+    in: |
+      4
+
+extend: []
+"#,
+            &TestSuite::Batch(BatchTestSuite {
+                timelimit: Some(Duration::from_secs(2)),
+                memorylimit: None,
+                outputlimit: None,
+                inputfile: None,
+                outputfile: None,
+                r#match: Match::Checker {
+                    command: vec!["python3".to_owned(), "./checker.py".to_owned()],
+                },
+                cases: vec![
+                    PartialBatchTestCase {
+                        name: Some("Sample 1".to_owned()),
+                        r#in: "3\n".into(),
+                        out: vec!["1 2 3\n".into()],
+                        timelimit: None,
+                        memorylimit: None,
+                        r#match: None,
+                    },
+                    PartialBatchTestCase {
+                        name: Some("Sample 2".to_owned()),
+                        r#in: "4\n".into(),
+                        out: vec![],
+                        timelimit: None,
+                        memorylimit: None,
+                        r#match: None,
+                    },
+                ],
+                extend: vec![],
+            }),
+        );
+    }
+
+    #[test]
+    fn empty_checker_command() {
+        let suite = BatchTestSuite {
+            timelimit: None,
+            memorylimit: None,
+            outputlimit: None,
+            inputfile: None,
+            outputfile: None,
+            r#match: Match::Checker { command: vec![] },
+            cases: vec![PartialBatchTestCase {
+                name: None,
+                r#in: "".into(),
+                out: vec![],
+                timelimit: None,
+                memorylimit: None,
+                r#match: None,
+            }],
+            extend: vec![],
+        };
+
+        assert!(suite
+            .load_test_cases::<String>(Path::new(""), None)
+            .is_err());
+    }
+
+    #[test]
+    fn file_io_names() {
+        let suite = |inputfile: &str| BatchTestSuite {
+            timelimit: None,
+            memorylimit: None,
+            outputlimit: None,
+            inputfile: Some(inputfile.to_owned()),
+            outputfile: None,
+            r#match: Match::Lines,
+            cases: vec![],
+            extend: vec![],
+        };
+
+        for ok in &["input.txt", "input"] {
+            assert!(suite(ok)
+                .load_test_cases::<String>(Path::new(""), None)
+                .is_ok());
+        }
+        for ng in &["", "..", "./input.txt", "dir/input.txt", "/tmp/input.txt"] {
+            assert!(suite(ng)
+                .load_test_cases::<String>(Path::new(""), None)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn multiple_outputs() {
+        test_serialize_deserialize(
+            r#"---
+type: Batch
+timelimit: 2s
+match: Lines
+
+cases:
+  - name: Sample 1
+    in: |
+      4
+    out:
+      - "1 3\n"
+      - "3 1\n"
+
+extend: []
+"#,
+            &TestSuite::Batch(BatchTestSuite {
+                timelimit: Some(Duration::from_secs(2)),
+                memorylimit: None,
+                outputlimit: None,
+                inputfile: None,
+                outputfile: None,
+                r#match: Match::Lines,
+                cases: vec![PartialBatchTestCase {
+                    name: Some("Sample 1".to_owned()),
+                    r#in: "4\n".into(),
+                    out: vec!["1 3\n".into(), "3 1\n".into()],
+                    timelimit: None,
+                    memorylimit: None,
+                    r#match: None,
+                }],
+                extend: vec![],
+            }),
+        );
+    }
+
+    #[test]
+    fn file_io() {
+        test_serialize_deserialize(
+            r#"---
+type: Batch
+timelimit: 2s
+inputfile: input.txt
+outputfile: output.txt
+match: Lines
+
+cases:
+  - name: Sample 1
+    in: |
+      4
+    out: |
+      2
+
+extend: []
+"#,
+            &TestSuite::Batch(BatchTestSuite {
+                timelimit: Some(Duration::from_secs(2)),
+                memorylimit: None,
+                outputlimit: None,
+                inputfile: Some("input.txt".to_owned()),
+                outputfile: Some("output.txt".to_owned()),
+                r#match: Match::Lines,
+                cases: vec![PartialBatchTestCase {
+                    name: Some("Sample 1".to_owned()),
+                    r#in: "4\n".into(),
+                    out: vec!["2\n".into()],
+                    timelimit: None,
+                    memorylimit: None,
+                    r#match: None,
+                }],
+                extend: vec![],
+            }),
+        );
+    }
+
+    #[test]
+    fn batch_load_test_cases_limits() {
+        let test_suite = serde_yaml::from_str::<TestSuite>(
+            r#"---
+type: Batch
+timelimit: 2s
+memorylimit: 1024 MiB
+outputlimit: 1 MiB
+match: Exact
+cases:
+  - name: Sample 1
+    in: |
+      3
+    out: |
+      3
+  - name: Sample 2
+    in: |
+      5
+    out: |
+      5
+    memorylimit: 256 KiB
+"#,
+        )
+        .unwrap();
+
+        let test_suite = match test_suite {
+            TestSuite::Batch(test_suite) => test_suite,
+            _ => panic!("expected `Batch`"),
+        };
+
+        let limits = test_suite
+            .load_test_cases::<&str>(Path::new("/tests"), None)
+            .unwrap()
+            .into_iter()
+            .map(|case| (case.memorylimit, case.outputlimit))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (Some(1024 * 1024 * 1024), Some(1024 * 1024)),
+                (Some(256 * 1024), Some(1024 * 1024)),
+            ],
+            limits,
+        );
+    }
+
+    #[test]
+    fn interactive_load_test_cases() {
+        let test_suite = serde_yaml::from_str::<TestSuite>(
+            r#"---
+type: Interactive
+timelimit: 2s
+interactor: [python3, ./tester.py]
+cases:
+  - name: Sample 1
+    in: |
+      3
+  - name: Sample 2
+    in: |
+      5
+    timelimit: 3s
+"#,
+        )
+        .unwrap();
+
+        let test_suite = match test_suite {
+            TestSuite::Interactive(test_suite) => test_suite,
+            _ => panic!("expected `Interactive`"),
+        };
+
+        let test_cases = test_suite
+            .load_test_cases(Path::new("/tests"), Some(hashset!("Sample 2")))
+            .unwrap();
+
+        assert_eq!(
+            vec![InteractiveTestCase {
+                name: Some("Sample 2".to_owned()),
+                timelimit: Some(Duration::from_secs(3)),
+                input: "5\n".into(),
+                interactor: CommandExpression {
+                    program: "python3".into(),
+                    args: vec!["./tester.py".into()],
+                    cwd: "/tests".into(),
+                    env: BTreeMap::new(),
+                    resource_limits: ResourceLimits::default(),
+                },
+            }],
+            test_cases,
+        );
+
+        assert!(InteractiveTestSuite {
+            timelimit: None,
+            interactor: None,
+            cases: vec![],
+        }
+        .load_test_cases::<&str>(Path::new("/tests"), None)
+        .is_err());
+    }
+
     fn test_serialize_deserialize(yaml: &str, expected: &TestSuite) {
         let actual = serde_yaml::from_str::<TestSuite>(yaml).unwrap();
         assert_eq!(*expected, actual);
@@ -759,5 +1470,112 @@ extend: []
             absolute_error: None,
         }
         .accepts("0\n"));
+
+        assert!(ExpectedOutput::Tokens {
+            text: "1 2\n".into()
+        }
+        .accepts("1\n  2"));
+
+        assert!(!ExpectedOutput::Tokens {
+            text: "1 2\n".into()
+        }
+        .accepts("12\n"));
+
+        assert!(ExpectedOutput::CaseInsensitive {
+            text: "Yes\n".into()
+        }
+        .accepts("YES\n"));
+
+        assert!(!ExpectedOutput::CaseInsensitive {
+            text: "Yes\n".into()
+        }
+        .accepts("Y e s\n"));
+
+        assert!(ExpectedOutput::UnorderedLines {
+            text: "1 2\n3 4\n3 4\n".into()
+        }
+        .accepts("3 4\n1 2\n3 4\n"));
+
+        assert!(!ExpectedOutput::UnorderedLines {
+            text: "1 2\n3 4\n3 4\n".into()
+        }
+        .accepts("3 4\n1 2\n1 2\n"));
+
+        assert!(ExpectedOutput::Regex {
+            text: "[0-9]+\nYes|No\n".into(),
+            patterns: LineRegexes::new("[0-9]+\nYes|No\n"),
+        }
+        .accepts("42\nNo\n"));
+
+        assert!(!ExpectedOutput::Regex {
+            text: "[0-9]+\n".into(),
+            patterns: LineRegexes::new("[0-9]+\n"),
+        }
+        .accepts("42a\n"));
+
+        let any_of = ExpectedOutput::AnyOf {
+            alternatives: vec![
+                ExpectedOutput::Lines {
+                    text: "1 3\n".into(),
+                },
+                ExpectedOutput::Lines {
+                    text: "3 1\n".into(),
+                },
+            ],
+        };
+        assert!(any_of.accepts("3 1\n"));
+        assert!(!any_of.accepts("2 2\n"));
+    }
+
+    #[test]
+    fn batch_load_test_cases_invalid_regex() {
+        let test_suite = BatchTestSuite {
+            timelimit: None,
+            memorylimit: None,
+            outputlimit: None,
+            inputfile: None,
+            outputfile: None,
+            r#match: Match::Regex,
+            cases: vec![PartialBatchTestCase {
+                name: Some("Sample 1".to_owned()),
+                r#in: "\n".into(),
+                out: vec!["(\n".into()],
+                timelimit: None,
+                memorylimit: None,
+                r#match: None,
+            }],
+            extend: vec![],
+        };
+
+        assert!(test_suite
+            .load_test_cases::<&str>(Path::new("/tests"), None)
+            .is_err());
+    }
+
+    #[test]
+    fn batch_load_test_cases_checker_with_multiple_outs() {
+        let test_suite = BatchTestSuite {
+            timelimit: None,
+            memorylimit: None,
+            outputlimit: None,
+            inputfile: None,
+            outputfile: None,
+            r#match: Match::Checker {
+                command: vec!["./checker".to_owned()],
+            },
+            cases: vec![PartialBatchTestCase {
+                name: Some("Sample 1".to_owned()),
+                r#in: "\n".into(),
+                out: vec!["1\n".into(), "2\n".into()],
+                timelimit: None,
+                memorylimit: None,
+                r#match: None,
+            }],
+            extend: vec![],
+        };
+
+        assert!(test_suite
+            .load_test_cases::<&str>(Path::new("/tests"), None)
+            .is_err());
     }
 }
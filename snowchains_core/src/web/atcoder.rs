@@ -15,6 +15,7 @@ use crate::{
 use anyhow::{anyhow, bail, Context as _};
 use chrono::{DateTime, FixedOffset, Local, Utc};
 use easy_ext::ext;
+use human_size::{Any, Size};
 use indexmap::{indexmap, IndexMap};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use itertools::Itertools as _;
@@ -593,6 +594,10 @@ fn retrieve_sample_test_cases(
 
                                 TestSuite::Batch(BatchTestSuite {
                                     timelimit: None,
+                                    memorylimit: None,
+                                    outputlimit: None,
+                                    inputfile: None,
+                                    outputfile: None,
                                     r#match: Match::Lines,
                                     cases: vec![],
                                     extend: vec![],
@@ -1329,6 +1334,12 @@ impl Html {
                         .exactly_one()
                         .map_err(|_| "Could not extract the timelimit")?;
 
+                    let memorylimit = div
+                        .select(static_selector!(":scope > p"))
+                        .flat_map(|r| r.text())
+                        .flat_map(parse_memorylimit)
+                        .next();
+
                     // In `tasks_print`, there are multiple `#task-statement`s.
                     let samples = div
                         .select(static_selector!(":scope > div[id=\"task-statement\"]"))
@@ -1342,6 +1353,10 @@ impl Html {
                     } else if let Samples::Batch(r#match, samples) = samples {
                         TestSuite::Batch(BatchTestSuite {
                             timelimit: Some(timelimit),
+                            memorylimit,
+                            outputlimit: None,
+                            inputfile: None,
+                            outputfile: None,
                             r#match,
                             cases: samples
                                 .into_iter()
@@ -1349,8 +1364,9 @@ impl Html {
                                 .map(|(i, (input, output))| PartialBatchTestCase {
                                     name: Some(format!("sample{}", i + 1)),
                                     r#in: input.into(),
-                                    out: Some(output.into()),
+                                    out: vec![output.into()],
                                     timelimit: None,
+                                    memorylimit: None,
                                     r#match: None,
                                 })
                                 .collect(),
@@ -1359,6 +1375,8 @@ impl Html {
                     } else {
                         TestSuite::Interactive(InteractiveTestSuite {
                             timelimit: Some(timelimit),
+                            interactor: None,
+                            cases: vec![],
                         })
                     })
                 })()
@@ -1389,6 +1407,12 @@ impl Html {
             Some(Duration::from_millis(timelimit))
         }
 
+        fn parse_memorylimit(text: &str) -> Option<Size> {
+            let caps = static_regex!(r"(?:Memory Limit|メモリ制限)\s*:\s*([0-9]{1,9})\s*Mi?B")
+                .captures(text)?;
+            Size::new(caps[1].parse::<u32>().unwrap(), Any::Mebibyte).ok()
+        }
+
         fn extract_samples(task_statement: ElementRef<'_>) -> Option<Samples> {
             // TODO:
             // - https://atcoder.jp/contests/arc019/tasks/arc019_4 (interactive)
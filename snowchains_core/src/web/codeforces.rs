@@ -10,6 +10,7 @@ use crate::{
 };
 use anyhow::{bail, Context as _};
 use easy_ext::ext;
+use human_size::{Any, Size};
 use indexmap::{indexmap, IndexMap};
 use itertools::Itertools as _;
 use once_cell::sync::Lazy;
@@ -533,6 +534,16 @@ impl Html {
             .ok()
             .with_context(|| "Could not extract the timelimit")?;
 
+        let memorylimit = self
+            .select(static_selector!("#pageContent div.memory-limit"))
+            .flat_map(|r| r.text())
+            .flat_map(|text| {
+                let caps = lazy_regex!(r#"\A([0-9]{1,9}) megabytes?\z"#).captures(text)?;
+                Size::new(caps[1].parse::<u32>().unwrap(), Any::Mebibyte).ok()
+            })
+            .exactly_one()
+            .ok();
+
         let input_file_text = self
             .select(static_selector!("#pageContent div.input-file"))
             .flat_map(|r| r.children())
@@ -551,11 +562,11 @@ impl Html {
             })
             .collect::<Vec<_>>();
 
-        if !(input_file_text.contains(&"standard input")
-            && output_file_text.contains(&"standard output"))
-        {
-            todo!();
-        }
+        let inputfile = file_name(&input_file_text, "standard input")
+            .with_context(|| "Could not extract the input file")?;
+        let outputfile = file_name(&output_file_text, "standard output")
+            .with_context(|| "Could not extract the output file")?;
+
         let r#match = Match::Lines;
 
         let sample_test = self
@@ -585,19 +596,41 @@ impl Html {
             .map(|(i, (r#in, out))| PartialBatchTestCase {
                 name: Some(format!("example{}", i + 1)),
                 r#in: r#in.into(),
-                out: Some(out.into()),
+                out: vec![out.into()],
                 r#match: None,
                 timelimit: None,
+                memorylimit: None,
             })
             .collect();
 
         return Ok(TestSuite::Batch(BatchTestSuite {
             timelimit: Some(timelimit),
+            memorylimit,
+            outputlimit: None,
+            inputfile,
+            outputfile,
             r#match,
             cases,
             extend: vec![],
         }));
 
+        /// Returns `None` for `standard input` and `standard output`.
+        fn file_name(texts: &[&str], standard: &str) -> anyhow::Result<Option<String>> {
+            let text = texts
+                .iter()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .exactly_one()
+                .ok()
+                .with_context(|| format!("Expected exactly one text: {:?}", texts))?;
+
+            Ok(if text == standard {
+                None
+            } else {
+                Some(text.to_owned())
+            })
+        }
+
         #[ext]
         impl ElementRef<'_> {
             fn fold_text_and_br(&self) -> String {
@@ -11,6 +11,7 @@ use crate::{
 use anyhow::{bail, Context as _};
 use easy_ext::ext;
 use either::Either;
+use human_size::{Any, Size};
 use indexmap::indexmap;
 use itertools::Itertools as _;
 use once_cell::sync::Lazy;
@@ -414,7 +415,7 @@ impl Html {
     }
 
     fn extract_samples(&self) -> anyhow::Result<TestSuite> {
-        let (timelimit, kind) = self
+        let (timelimit, memorylimit, kind) = self
             .select(static_selector!("#content > div"))
             .flat_map(|r| r.text())
             .nth(1)
@@ -428,6 +429,12 @@ impl Html {
                     Duration::from_millis(1000 * secs + millis)
                 };
 
+                let memorylimit = static_regex!(r"メモリー?制限\s*:\s*([0-9]{1,9})\s*MB")
+                    .captures(text)
+                    .and_then(|caps| {
+                        Size::new(caps[1].parse::<u32>().unwrap(), Any::Mebibyte).ok()
+                    });
+
                 let kind = {
                     let caps = static_regex!("(通常|スペシャルジャッジ|リアクティブ)問題")
                         .captures(text)?;
@@ -440,7 +447,7 @@ impl Html {
                     }
                 };
 
-                Some((timelimit, kind))
+                Some((timelimit, memorylimit, kind))
             })
             .with_context(|| "Could not parse the page")?;
 
@@ -448,6 +455,10 @@ impl Html {
             Kind::Regular | Kind::Special => {
                 let mut test_suite = BatchTestSuite {
                     timelimit: Some(timelimit),
+                    memorylimit,
+                    outputlimit: None,
+                    inputfile: None,
+                    outputfile: None,
                     r#match: Match::Lines,
                     cases: vec![],
                     extend: vec![],
@@ -467,11 +478,12 @@ impl Html {
                             name: Some(format!("sample{}", i + 1)),
                             r#in: input.fold_text_and_br().into(),
                             out: if kind == Kind::Regular {
-                                Some(output.fold_text_and_br().into())
+                                vec![output.fold_text_and_br().into()]
                             } else {
-                                None
+                                vec![]
                             },
                             timelimit: None,
+                            memorylimit: None,
                             r#match: None,
                         });
                     } else {
@@ -483,6 +495,8 @@ impl Html {
             }
             Kind::Reactive => TestSuite::Interactive(InteractiveTestSuite {
                 timelimit: Some(timelimit),
+                interactor: None,
+                cases: vec![],
             }),
         };
 
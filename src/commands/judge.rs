@@ -1,9 +1,12 @@
 use crate::config;
-use human_size::Size;
-use snowchains_core::web::PlatformKind;
-use std::path::PathBuf;
+use anyhow::{bail, Context as _};
+use az::SaturatingAs as _;
+use human_size::{Byte, Size};
+use indicatif::ProgressDrawTarget;
+use snowchains_core::{judge::SandboxOptions, web::PlatformKind};
+use std::{num::NonZeroUsize, path::PathBuf, str::FromStr, time::Duration};
 use structopt::StructOpt;
-use strum::VariantNames as _;
+use strum::{EnumString, EnumVariantNames, VariantNames as _};
 use termcolor::WriteColor;
 
 #[derive(StructOpt, Debug)]
@@ -12,14 +15,74 @@ pub struct OptJudge {
     #[structopt(long)]
     pub release: bool,
 
+    /// Prints JSON data
+    #[structopt(long)]
+    pub json: bool,
+
     /// Test for only the test cases
     #[structopt(long, value_name("NAME"))]
     pub testcases: Option<Vec<String>>,
 
+    /// Writes a report (`junit` or `tap`) to the path. Without a path, writes it to stdout instead of the result
+    #[structopt(long, value_name("FORMAT[=PATH]"), number_of_values(1))]
+    pub report: Vec<Report>,
+
     /// Display limit
     #[structopt(long, value_name("SIZE"), default_value("4KiB"))]
     pub display_limit: Size,
 
+    /// Judge `timelimit` on CPU time instead of wall-clock time
+    #[structopt(long)]
+    pub cpu_time: bool,
+
+    /// Output limit for each of stdout and stderr. Overrides `outputlimit` in the test file
+    #[structopt(long, value_name("SIZE"))]
+    pub output_limit: Option<Size>,
+
+    /// Number of test cases run at the same time [default: the number of CPUs]
+    #[structopt(long, value_name("N"))]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Cancel the rest of the test cases after the first failure
+    #[structopt(long)]
+    pub fail_fast: bool,
+
+    /// Show one progress bar for all of the test cases when there are more than N, listing only the failures
+    #[structopt(long, value_name("N"), default_value("100"))]
+    pub compact_progress_above: usize,
+
+    /// Run every test case N times and show statistics of the elapsed times
+    #[structopt(long, value_name("N"), default_value("1"))]
+    pub repeat: NonZeroUsize,
+
+    /// With `--repeat`, flag test cases whose slowest run exceeds this fraction of `timelimit`
+    #[structopt(long, value_name("RATIO"), default_value("0.8"))]
+    pub near_timelimit: f64,
+
+    /// Run the solution without network access, where only `$TMPDIR` is writable (Linux only)
+    #[structopt(long)]
+    pub sandbox: bool,
+
+    /// Address space limit in the sandbox
+    #[structopt(long, value_name("SIZE"))]
+    pub sandbox_address_space: Option<Size>,
+
+    /// Process (and thread) limit in the sandbox
+    #[structopt(long, value_name("N"), default_value("256"))]
+    pub sandbox_processes: u64,
+
+    /// File size limit in the sandbox
+    #[structopt(long, value_name("SIZE"), default_value("64MiB"))]
+    pub sandbox_file_size: Size,
+
+    /// Build even if the source file and the build command are unchanged
+    #[structopt(long)]
+    pub force_build: bool,
+
+    /// Watch the source file and the test file, and rerun the tests on every change
+    #[structopt(long, conflicts_with("json"))]
+    pub watch: bool,
+
     /// Path to `snowchains.dhall`
     #[structopt(long)]
     pub config: Option<PathBuf>,
@@ -53,14 +116,63 @@ pub struct OptJudge {
     pub problem: Option<String>,
 }
 
+#[derive(Debug)]
+pub struct Report {
+    pub format: ReportFormat,
+    pub path: Option<PathBuf>,
+}
+
+impl FromStr for Report {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (format, path) = match s.find('=') {
+            Some(i) => (&s[..i], Some(s[i + 1..].into())),
+            None => (s, None),
+        };
+
+        let format = format.parse().with_context(|| {
+            format!(
+                "Unknown format: {:?} (expected one of [{}])",
+                format,
+                ReportFormat::VARIANTS.join(", "),
+            )
+        })?;
+
+        Ok(Self { format, path })
+    }
+}
+
+#[derive(EnumVariantNames, EnumString, Debug, Clone, Copy)]
+#[strum(serialize_all = "lowercase")]
+pub enum ReportFormat {
+    Junit,
+    Tap,
+}
+
 pub(crate) fn run(
     opt: OptJudge,
     ctx: crate::Context<impl Sized, impl WriteColor, impl WriteColor>,
 ) -> anyhow::Result<()> {
     let OptJudge {
         release,
+        json,
         testcases,
+        report,
         display_limit,
+        cpu_time,
+        output_limit,
+        jobs,
+        fail_fast,
+        compact_progress_above,
+        repeat,
+        near_timelimit,
+        sandbox,
+        sandbox_address_space,
+        sandbox_processes,
+        sandbox_file_size,
+        force_build,
+        watch,
         config,
         color: _,
         service,
@@ -71,17 +183,33 @@ pub(crate) fn run(
 
     let crate::Context { cwd, shell } = ctx;
 
-    let progress_draw_target = shell.progress_draw_target();
+    if json && report.iter().any(|r| r.path.is_none()) {
+        bail!("`--json` and `--report` without a path cannot be used together");
+    }
+
+    let reports = report
+        .into_iter()
+        .map(|Report { format, path }| (format, path.map(|p| cwd.join(p))))
+        .collect::<Vec<_>>();
 
     let crate::shell::Shell {
-        stdout,
-        stderr,
+        stdin,
+        mut stdout,
+        mut stderr,
+        stderr_tty,
         stdin_process_redirection,
         stdout_process_redirection,
         stderr_process_redirection,
-        ..
     } = shell;
 
+    let progress_draw_target = || {
+        if stderr_tty {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        }
+    };
+
     let (
         config::Target {
             service,
@@ -89,13 +217,7 @@ pub(crate) fn run(
             problem,
             ..
         },
-        config::Language {
-            src,
-            transpile,
-            compile,
-            run,
-            languageId: _,
-        },
+        language,
         base_dir,
     ) = config::target_and_language(
         &cwd,
@@ -111,24 +233,86 @@ pub(crate) fn run(
         },
     )?;
 
-    let test_case_names = testcases.map(|ss| ss.into_iter().collect());
+    let resource_limits = language.resource_limits();
 
-    crate::judge::judge(crate::judge::Args {
-        stdout,
-        stderr,
-        stdin_process_redirection,
-        stdout_process_redirection,
-        stderr_process_redirection,
-        progress_draw_target,
-        base_dir,
-        service,
-        contest,
-        problem,
+    let config::Language {
         src,
         transpile,
         compile,
         run,
-        test_case_names,
-        display_limit,
-    })
+        languageId: _,
+        timelimitMultiplier: timelimit_multiplier,
+        timelimitMarginMs: timelimit_margin_ms,
+        warmUpRuns: warm_up_runs,
+        stackSizeMib: _,
+        addressSpaceMib: _,
+        openFiles: _,
+        cpuTimeSecs: _,
+    } = language;
+
+    let test_case_names = testcases.map(|ss| ss.into_iter().collect());
+
+    let bytes = |size: Size| size.into::<Byte>().value().saturating_as();
+
+    let sandbox = if sandbox {
+        Some(SandboxOptions {
+            address_space: sandbox_address_space.map(bytes),
+            processes: Some(sandbox_processes),
+            file_size: Some(bytes(sandbox_file_size)),
+        })
+    } else {
+        None
+    };
+
+    let mut judge = |stderr: &mut _, test_case_names| {
+        crate::judge::judge(crate::judge::Args {
+            stdout: &mut stdout,
+            stderr,
+            stdin_process_redirection,
+            stdout_process_redirection,
+            stderr_process_redirection,
+            progress_draw_target: progress_draw_target(),
+            base_dir: base_dir.clone(),
+            service,
+            contest: contest.clone(),
+            problem: problem.clone(),
+            src: src.clone(),
+            transpile: transpile.clone(),
+            compile: compile.clone(),
+            run: run.clone(),
+            resource_limits,
+            force_build,
+            timelimit_multiplier,
+            timelimit_margin: timelimit_margin_ms.map(Duration::from_millis),
+            warm_up_runs: warm_up_runs.unwrap_or(0),
+            test_case_names,
+            json,
+            reports: reports.clone(),
+            display_limit,
+            cpu_time,
+            output_limit,
+            jobs,
+            fail_fast,
+            repeat,
+            near_timelimit,
+            sandbox,
+            compact_progress_above: Some(compact_progress_above),
+        })
+    };
+
+    if watch {
+        let watched_paths =
+            || crate::judge::watched_paths(&base_dir, service, contest.as_deref(), &problem, &src);
+
+        crate::judge::watch(
+            stderr,
+            &stdin,
+            stderr_tty,
+            watched_paths,
+            test_case_names,
+            judge,
+        )
+    } else {
+        judge(&mut stderr, test_case_names)?.error_on_fail()
+    }
 }
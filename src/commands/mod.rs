@@ -5,6 +5,8 @@ pub(crate) mod participate;
 pub(crate) mod retrieve_languages;
 pub(crate) mod retrieve_submission_summaries;
 pub(crate) mod retrieve_testcases;
+pub(crate) mod shrink;
+pub(crate) mod stress;
 pub(crate) mod submit;
 pub(crate) mod watch_submissions;
 pub(crate) mod xtask;
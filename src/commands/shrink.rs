@@ -0,0 +1,337 @@
+use crate::config;
+use anyhow::{bail, Context as _};
+use az::SaturatingAs as _;
+use human_size::{Byte, Size};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use maplit::hashset;
+use snowchains_core::{
+    color_spec,
+    judge::{CommandExpression, JudgeOptions, JudgeOutcome, ShrinkOutcome},
+    testsuite::{BatchTestCase, BatchTestSuite, PartialBatchTestCase, TestSuite},
+    web::PlatformKind,
+};
+use std::{
+    mem,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+use strum::VariantNames as _;
+use termcolor::{Color, WriteColor};
+
+#[derive(StructOpt, Debug)]
+pub struct OptShrink {
+    /// Build in `Release` mode
+    #[structopt(long)]
+    pub release: bool,
+
+    /// Name of the failing test case
+    #[structopt(long, value_name("NAME"))]
+    pub testcase: String,
+
+    /// Language name of the reference solution. Required to shrink `Wrong Answer`
+    #[structopt(long, value_name("STRING"))]
+    pub reference: Option<String>,
+
+    /// Language name of a program that reads an input from stdin and prints a smaller one
+    #[structopt(long, value_name("STRING"))]
+    pub shrinker: Option<String>,
+
+    /// Display limit
+    #[structopt(long, value_name("SIZE"), default_value("4KiB"))]
+    pub display_limit: Size,
+
+    /// Judge `timelimit` on CPU time instead of wall-clock time
+    #[structopt(long)]
+    pub cpu_time: bool,
+
+    /// Number of candidates tested at the same time [default: the number of CPUs]
+    #[structopt(long, value_name("N"))]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(
+        short,
+        long,
+        value_name("SERVICE"),
+        possible_values(PlatformKind::KEBAB_CASE_VARIANTS)
+    )]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+
+    /// Language name
+    #[structopt(short, long, value_name("STRING"))]
+    pub language: Option<String>,
+
+    /// Problem index (e.g. "a", "b", "c")
+    pub problem: Option<String>,
+}
+
+pub(crate) fn run(
+    opt: OptShrink,
+    ctx: crate::Context<impl Sized, impl WriteColor, impl WriteColor>,
+) -> anyhow::Result<()> {
+    let OptShrink {
+        release,
+        testcase,
+        reference,
+        shrinker,
+        display_limit,
+        cpu_time,
+        jobs,
+        config,
+        color: _,
+        service,
+        contest,
+        language,
+        problem,
+    } = opt;
+
+    let crate::Context { cwd, shell } = ctx;
+
+    let progress_draw_target = shell.progress_draw_target();
+
+    let crate::shell::Shell {
+        mut stdout,
+        mut stderr,
+        stdin_process_redirection,
+        stdout_process_redirection,
+        stderr_process_redirection,
+        ..
+    } = shell;
+
+    let mode = if release {
+        config::Mode::Release
+    } else {
+        config::Mode::Debug
+    };
+
+    let target_and_language = |language: Option<&str>| {
+        config::target_and_language(
+            &cwd,
+            config.as_deref(),
+            service,
+            contest.as_deref(),
+            problem.as_deref(),
+            language,
+            mode,
+        )
+    };
+
+    let (target, solution, base_dir) = target_and_language(language.as_deref())?;
+    let reference = reference
+        .map(|name| target_and_language(Some(&name)).map(|(_, language, _)| language))
+        .transpose()?;
+    let shrinker = shrinker
+        .map(|name| target_and_language(Some(&name)).map(|(_, language, _)| language))
+        .transpose()?;
+
+    let config::Target {
+        service,
+        contest,
+        problem,
+        ..
+    } = target;
+
+    let test_suite_dir = base_dir
+        .join(".snowchains")
+        .join("tests")
+        .join(service.to_kebab_case_str())
+        .join(contest.as_deref().unwrap_or(""));
+    let test_suite_path = test_suite_dir.join(&problem).with_extension("yml");
+
+    let test_suite = match crate::fs::read_yaml(&test_suite_path)? {
+        TestSuite::Batch(test_suite) => test_suite,
+        _ => bail!("`{}` is not a batch test suite", test_suite_path.display()),
+    };
+
+    let test_case = test_suite
+        .load_test_cases(&test_suite_dir, Some(hashset!(&*testcase)))?
+        .pop()
+        .with_context(|| format!("No such test case: {:?}", testcase))?;
+
+    let redirections = (
+        stdin_process_redirection,
+        stdout_process_redirection,
+        stderr_process_redirection,
+    );
+
+    let mut newline = false;
+
+    let mut prepare = |language, name| {
+        super::stress::prepare(
+            &mut stderr,
+            &base_dir,
+            language,
+            name,
+            redirections,
+            &mut newline,
+        )
+    };
+
+    let (solution, _solution_tempfile) = prepare(solution, "Solution")?;
+    let (reference, _reference_tempfile) = match reference {
+        Some(reference) => {
+            let (cmd, tempfile) = prepare(reference, "Reference")?;
+            (Some(cmd), tempfile)
+        }
+        None => (None, None),
+    };
+    let (shrinker, _shrinker_tempfile) = match shrinker {
+        Some(shrinker) => {
+            let (cmd, tempfile) = prepare(shrinker, "Shrinker")?;
+            (Some(cmd), tempfile)
+        }
+        None => (None, None),
+    };
+
+    if mem::replace(&mut newline, true) {
+        writeln!(stderr)?;
+    }
+    stderr.set_color(color_spec!(Bold))?;
+    write!(stderr, "Shrinking...")?;
+    stderr.reset()?;
+    writeln!(stderr)?;
+
+    stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
+    write!(stderr, "Test file:")?;
+    stderr.reset()?;
+    writeln!(stderr, " {}", test_suite_path.display())?;
+
+    stderr.flush()?;
+
+    let ShrinkOutcome {
+        test_case: shrunk,
+        verdict,
+        steps,
+    } = shrink(
+        progress_draw_target,
+        &solution,
+        reference.as_ref(),
+        shrinker.as_ref(),
+        &test_case,
+        JudgeOptions {
+            timelimit_on_cpu_time: cpu_time,
+            output_limit: None,
+            jobs,
+            fail_fast: false,
+            sandbox: None,
+            compact_progress_above: None,
+        },
+    )?;
+
+    JudgeOutcome {
+        verdicts: vec![verdict],
+    }
+    .print_pretty(
+        &mut stdout,
+        Some(display_limit.into::<Byte>().value().saturating_as()),
+        None,
+    )?;
+    stdout.flush()?;
+
+    writeln!(stderr)?;
+    writeln!(
+        stderr,
+        "Shrunk the input from {} bytes to {} bytes in {} step{}",
+        test_case.input.len(),
+        shrunk.input.len(),
+        steps,
+        if steps == 1 { "" } else { "s" },
+    )?;
+
+    let original = test_suite
+        .cases
+        .iter()
+        .find(|c| c.name.as_ref() == Some(&testcase))
+        .cloned();
+
+    let case = PartialBatchTestCase {
+        name: Some(format!("{}-shrunk", testcase)),
+        r#in: shrunk.input.clone(),
+        out: reference
+            .and(shrunk.output.text().cloned())
+            .into_iter()
+            .collect(),
+        timelimit: original.as_ref().and_then(|c| c.timelimit),
+        memorylimit: original.as_ref().and_then(|c| c.memorylimit),
+        r#match: original.and_then(|c| c.r#match),
+    };
+
+    save_case(&mut stderr, test_suite, &test_suite_path, case)
+}
+
+pub(super) fn shrink(
+    progress_draw_target: ProgressDrawTarget,
+    solution: &CommandExpression,
+    reference: Option<&CommandExpression>,
+    shrinker: Option<&CommandExpression>,
+    test_case: &BatchTestCase,
+    options: JudgeOptions,
+) -> anyhow::Result<ShrinkOutcome> {
+    let progress = ProgressBar::new_spinner();
+    progress.set_draw_target(progress_draw_target);
+    progress.set_style(ProgressStyle::default_spinner().template("{spinner} {msg}"));
+    progress.set_message(&format!("Shrinking... ({} bytes)", test_case.input.len()));
+    progress.enable_steady_tick(50);
+
+    let outcome = snowchains_core::judge::shrink(
+        tokio::signal::ctrl_c,
+        solution,
+        reference,
+        shrinker,
+        test_case,
+        options,
+        |test_case| {
+            progress.set_message(&format!("Shrinking... ({} bytes)", test_case.input.len()))
+        },
+    );
+
+    progress.finish_and_clear();
+    outcome
+}
+
+/// Adds `case` to the test suite, replacing the one with the same name.
+pub(super) fn save_case(
+    mut stderr: impl WriteColor,
+    mut test_suite: BatchTestSuite,
+    test_suite_path: &Path,
+    case: PartialBatchTestCase,
+) -> anyhow::Result<()> {
+    let name = case.name.clone().unwrap_or_default();
+
+    let verb = if let Some(existing) = test_suite.cases.iter_mut().find(|c| c.name == case.name) {
+        *existing = case;
+        "Updated"
+    } else {
+        test_suite.cases.push(case);
+        "Added"
+    };
+
+    let yaml = TestSuite::Batch(test_suite).to_yaml_pretty();
+    crate::fs::write(test_suite_path, yaml, false)?;
+
+    write!(stderr, "{} ", verb)?;
+    stderr.set_color(color_spec!(Fg(Color::Cyan)))?;
+    write!(stderr, "{}", name)?;
+    stderr.reset()?;
+    writeln!(stderr, " in `{}`", test_suite_path.display())?;
+    stderr.flush()?;
+
+    Ok(())
+}
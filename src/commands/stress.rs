@@ -0,0 +1,457 @@
+use crate::config;
+use anyhow::{bail, Context as _};
+use az::SaturatingAs as _;
+use human_size::{Byte, Size};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use snowchains_core::{
+    color_spec,
+    judge::{CommandExpression, JudgeOptions, JudgeOutcome, Verdict},
+    testsuite::{BatchTestCase, PartialBatchTestCase, TestSuite},
+    web::PlatformKind,
+};
+use std::{
+    cmp, mem,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+use strum::VariantNames as _;
+use tempfile::NamedTempFile;
+use termcolor::{Color, WriteColor};
+
+#[derive(StructOpt, Debug)]
+pub struct OptStress {
+    /// Build in `Release` mode
+    #[structopt(long)]
+    pub release: bool,
+
+    /// Language name of the generator. The seed is passed as the last argument
+    #[structopt(long, value_name("STRING"))]
+    pub generator: String,
+
+    /// Language name of the reference solution
+    #[structopt(long, value_name("STRING"))]
+    pub reference: String,
+
+    /// Number of seeds
+    #[structopt(long, value_name("N"), default_value("100"))]
+    pub seeds: u64,
+
+    /// First seed
+    #[structopt(long, value_name("N"), default_value("0"))]
+    pub seed_start: u64,
+
+    /// Stop after the duration even if seeds are left
+    #[structopt(long, value_name("DURATION"), parse(try_from_str = humantime::parse_duration))]
+    pub time_budget: Option<Duration>,
+
+    /// Shrink the failing input before adding it
+    #[structopt(long)]
+    pub shrink: bool,
+
+    /// Display limit
+    #[structopt(long, value_name("SIZE"), default_value("4KiB"))]
+    pub display_limit: Size,
+
+    /// Judge `timelimit` on CPU time instead of wall-clock time
+    #[structopt(long)]
+    pub cpu_time: bool,
+
+    /// Number of seeds tested at the same time [default: the number of CPUs]
+    #[structopt(long, value_name("N"))]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(
+        short,
+        long,
+        value_name("SERVICE"),
+        possible_values(PlatformKind::KEBAB_CASE_VARIANTS)
+    )]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+
+    /// Language name
+    #[structopt(short, long, value_name("STRING"))]
+    pub language: Option<String>,
+
+    /// Problem index (e.g. "a", "b", "c")
+    pub problem: Option<String>,
+}
+
+pub(crate) fn run(
+    opt: OptStress,
+    ctx: crate::Context<impl Sized, impl WriteColor, impl WriteColor>,
+) -> anyhow::Result<()> {
+    let OptStress {
+        release,
+        generator,
+        reference,
+        seeds,
+        seed_start,
+        time_budget,
+        shrink,
+        display_limit,
+        cpu_time,
+        jobs,
+        config,
+        color: _,
+        service,
+        contest,
+        language,
+        problem,
+    } = opt;
+
+    let crate::Context { cwd, shell } = ctx;
+
+    let progress_draw_target = shell.progress_draw_target();
+    let shrink_progress_draw_target = shell.progress_draw_target();
+
+    let crate::shell::Shell {
+        mut stdout,
+        mut stderr,
+        stdin_process_redirection,
+        stdout_process_redirection,
+        stderr_process_redirection,
+        ..
+    } = shell;
+
+    let mode = if release {
+        config::Mode::Release
+    } else {
+        config::Mode::Debug
+    };
+
+    let target_and_language = |language: Option<&str>| {
+        config::target_and_language(
+            &cwd,
+            config.as_deref(),
+            service,
+            contest.as_deref(),
+            problem.as_deref(),
+            language,
+            mode,
+        )
+    };
+
+    let (target, solution, base_dir) = target_and_language(language.as_deref())?;
+    let (_, generator, _) = target_and_language(Some(&generator))?;
+    let (_, reference, _) = target_and_language(Some(&reference))?;
+
+    let config::Target {
+        service,
+        contest,
+        problem,
+        ..
+    } = target;
+
+    let test_suite_dir = base_dir
+        .join(".snowchains")
+        .join("tests")
+        .join(service.to_kebab_case_str())
+        .join(contest.as_deref().unwrap_or(""));
+    let test_suite_path = test_suite_dir.join(&problem).with_extension("yml");
+
+    let test_suite = match crate::fs::read_yaml(&test_suite_path)? {
+        TestSuite::Batch(test_suite) => test_suite,
+        _ => bail!("`{}` is not a batch test suite", test_suite_path.display()),
+    };
+
+    let redirections = (
+        stdin_process_redirection,
+        stdout_process_redirection,
+        stderr_process_redirection,
+    );
+
+    let mut newline = false;
+
+    let mut prepare = |language, name| {
+        self::prepare(
+            &mut stderr,
+            &base_dir,
+            language,
+            name,
+            redirections,
+            &mut newline,
+        )
+    };
+
+    let (generator, _generator_tempfile) = prepare(generator, "Generator")?;
+    let (reference, _reference_tempfile) = prepare(reference, "Reference")?;
+    let (solution, _solution_tempfile) = prepare(solution, "Solution")?;
+
+    if mem::replace(&mut newline, true) {
+        writeln!(stderr)?;
+    }
+    stderr.set_color(color_spec!(Bold))?;
+    write!(stderr, "Stress testing...")?;
+    stderr.reset()?;
+    writeln!(stderr)?;
+
+    stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
+    write!(stderr, "Test file:")?;
+    stderr.reset()?;
+    writeln!(stderr, " {}", test_suite_path.display())?;
+
+    stderr.flush()?;
+
+    let display_limit = display_limit.into::<Byte>().value().saturating_as();
+    let jobs = jobs.map_or_else(num_cpus::get, NonZeroUsize::get) as u64;
+
+    let options = JudgeOptions {
+        timelimit_on_cpu_time: cpu_time,
+        output_limit: None,
+        jobs: NonZeroUsize::new(jobs as _),
+        fail_fast: false,
+        sandbox: None,
+        compact_progress_above: None,
+    };
+
+    let progress = ProgressBar::with_draw_target(seeds, progress_draw_target);
+    progress
+        .set_style(ProgressStyle::default_bar().template("{prefix}{bar} {pos}/{len} {wide_msg}"));
+
+    let started = Instant::now();
+    let seeds = seed_start..seed_start.saturating_add(seeds);
+    let mut tested = 0;
+
+    for round in (seeds.start..seeds.end).step_by(jobs as _) {
+        if matches!(time_budget, Some(budget) if started.elapsed() >= budget) {
+            break;
+        }
+
+        let round = round..cmp::min(round.saturating_add(jobs), seeds.end);
+        progress.set_message(&format!("seeds: {}..{}", round.start, round.end));
+
+        let inputs = round
+            .clone()
+            .map(|seed| generate(&generator, seed))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let reference_outcome = judge(
+            &reference,
+            round
+                .clone()
+                .zip(&inputs)
+                .map(|(seed, input)| BatchTestCase {
+                    name: Some(seed.to_string()),
+                    timelimit: None,
+                    memorylimit: None,
+                    outputlimit: None,
+                    inputfile: test_suite.inputfile.clone(),
+                    outputfile: test_suite.outputfile.clone(),
+                    input: input.clone(),
+                    output: snowchains_core::testsuite::ExpectedOutput::Pass,
+                })
+                .collect(),
+            JudgeOptions {
+                jobs: NonZeroUsize::new(jobs as _),
+                ..JudgeOptions::default()
+            },
+        )?;
+
+        let mut outputs = vec![];
+
+        for (seed, verdict) in round.clone().zip(&reference_outcome.verdicts) {
+            if let Verdict::Accepted { stdout, .. } = verdict {
+                outputs.push(stdout.clone());
+            } else {
+                progress.finish_and_clear();
+                JudgeOutcome {
+                    verdicts: vec![verdict.clone()],
+                }
+                .print_pretty(&mut stderr, Some(display_limit), None)?;
+                stderr.flush()?;
+                bail!("The reference solution failed with seed {}", seed);
+            }
+        }
+
+        let cases = round
+            .clone()
+            .zip(inputs)
+            .zip(outputs)
+            .map(|((seed, input), output)| PartialBatchTestCase {
+                name: Some(format!("stress-{}", seed)),
+                r#in: input,
+                out: vec![output],
+                timelimit: None,
+                memorylimit: None,
+                r#match: None,
+            })
+            .collect::<Vec<_>>();
+
+        let outcome = judge(
+            &solution,
+            cases
+                .iter()
+                .map(|case| BatchTestCase::new(case.clone(), &test_suite, &test_suite_dir))
+                .collect(),
+            JudgeOptions {
+                fail_fast: true,
+                ..options
+            },
+        )?;
+
+        tested += round.end - round.start;
+        progress.set_position(tested);
+
+        let failure = cases
+            .into_iter()
+            .zip(outcome.verdicts)
+            .find(|(_, v)| !matches!(v, Verdict::Accepted { .. } | Verdict::Skipped { .. }));
+
+        if let Some((mut case, mut verdict)) = failure {
+            progress.finish_and_clear();
+
+            let seed = case.name.clone().unwrap_or_default();
+            let seed = seed.trim_start_matches("stress-").to_owned();
+
+            if shrink {
+                let outcome = super::shrink::shrink(
+                    shrink_progress_draw_target,
+                    &solution,
+                    Some(&reference),
+                    None,
+                    &BatchTestCase::new(case.clone(), &test_suite, &test_suite_dir),
+                    options,
+                )?;
+                case.r#in = outcome.test_case.input.clone();
+                case.out = outcome
+                    .test_case
+                    .output
+                    .text()
+                    .cloned()
+                    .into_iter()
+                    .collect();
+                verdict = outcome.verdict;
+            }
+
+            JudgeOutcome {
+                verdicts: vec![verdict],
+            }
+            .print_pretty(&mut stdout, Some(display_limit), None)?;
+            stdout.flush()?;
+
+            writeln!(stderr)?;
+            super::shrink::save_case(&mut stderr, test_suite, &test_suite_path, case)?;
+
+            bail!("Found a failing input with seed {}", seed);
+        }
+    }
+
+    progress.finish_and_clear();
+
+    writeln!(
+        stderr,
+        "No failing input found in {} seed{} ({} ms)",
+        tested,
+        if tested == 1 { "" } else { "s" },
+        started.elapsed().as_millis(),
+    )?;
+    stderr.flush()?;
+
+    return Ok(());
+
+    fn generate(generator: &CommandExpression, seed: u64) -> anyhow::Result<Arc<str>> {
+        let output = std::process::Command::new(&generator.program)
+            .args(&generator.args)
+            .arg(seed.to_string())
+            .current_dir(&generator.cwd)
+            .envs(&generator.env)
+            .stdin(Stdio::null())
+            .output()
+            .with_context(|| format!("Could not execute {:?}", generator.program))?;
+
+        if !output.status.success() {
+            bail!(
+                "The generator failed with seed {} ({}):\n{}",
+                seed,
+                output.status,
+                String::from_utf8_lossy(&output.stderr),
+            );
+        }
+
+        let input = String::from_utf8(output.stdout)
+            .with_context(|| format!("The generator wrote invalid UTF-8 with seed {}", seed))?;
+        Ok(input.into())
+    }
+
+    fn judge(
+        cmd: &CommandExpression,
+        test_cases: Vec<BatchTestCase>,
+        options: JudgeOptions,
+    ) -> anyhow::Result<JudgeOutcome> {
+        let test_cases = test_cases.into_iter().map(Into::into).collect::<Vec<_>>();
+        snowchains_core::judge::judge(
+            ProgressDrawTarget::hidden(),
+            tokio::signal::ctrl_c,
+            cmd,
+            &test_cases,
+            options,
+        )
+    }
+}
+
+/// Builds `language` if necessary and resolves its `run`.
+#[allow(clippy::type_complexity)]
+pub(super) fn prepare(
+    mut stderr: impl WriteColor,
+    base_dir: &Path,
+    language: config::Language,
+    name: &str,
+    redirections: (fn() -> Stdio, fn() -> Stdio, fn() -> Stdio),
+    newline: &mut bool,
+) -> anyhow::Result<(CommandExpression, Option<NamedTempFile>)> {
+    let resource_limits = language.resource_limits();
+
+    let config::Language {
+        src,
+        transpile,
+        compile,
+        run,
+        ..
+    } = language;
+
+    for (action, msg) in &[(transpile, "Transpiling..."), (compile, "Compiling...")] {
+        if let Some(action) = action {
+            if mem::replace(newline, true) {
+                writeln!(stderr)?;
+            }
+
+            stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
+            write!(stderr, "{}:", name)?;
+            stderr.reset()?;
+            writeln!(stderr, " {}", src)?;
+
+            crate::judge::build(
+                &mut stderr,
+                base_dir,
+                &src,
+                action,
+                redirections,
+                msg,
+                false,
+            )?;
+        }
+    }
+
+    crate::judge::command_expression(run, base_dir.to_owned(), resource_limits)
+}
@@ -6,7 +6,7 @@ use snowchains_core::web::{
     CodeforcesSubmitCredentials, CodeforcesSubmitTarget, CookieStorage, PlatformKind, Submit,
     Yukicoder, YukicoderSubmitCredentials, YukicoderSubmitTarget,
 };
-use std::{cell::RefCell, io::BufRead, path::PathBuf};
+use std::{cell::RefCell, io::BufRead, num::NonZeroUsize, path::PathBuf, time::Duration};
 use structopt::StructOpt;
 use strum::VariantNames as _;
 use termcolor::WriteColor;
@@ -21,6 +21,10 @@ pub struct OptSubmit {
     #[structopt(long)]
     pub no_judge: bool,
 
+    /// Build even if the source file and the build command are unchanged
+    #[structopt(long)]
+    pub force_build: bool,
+
     /// Tests code in `Debug` mode
     #[structopt(long)]
     pub debug: bool,
@@ -37,6 +41,22 @@ pub struct OptSubmit {
     #[structopt(long, value_name("SIZE"), default_value("4KiB"))]
     pub display_limit: Size,
 
+    /// Judge `timelimit` on CPU time instead of wall-clock time
+    #[structopt(long)]
+    pub cpu_time: bool,
+
+    /// Output limit for each of stdout and stderr. Overrides `outputlimit` in the test file
+    #[structopt(long, value_name("SIZE"))]
+    pub output_limit: Option<Size>,
+
+    /// Number of test cases run at the same time [default: the number of CPUs]
+    #[structopt(long, value_name("N"))]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Run the rest of the test cases after the first failure
+    #[structopt(long)]
+    pub no_fail_fast: bool,
+
     /// Path to `snowchains.dhall`
     #[structopt(long)]
     pub config: Option<PathBuf>,
@@ -77,10 +97,15 @@ pub(crate) fn run(
     let OptSubmit {
         no_watch,
         no_judge,
+        force_build,
         debug,
         json,
         testcases,
         display_limit,
+        cpu_time,
+        output_limit,
+        jobs,
+        no_fail_fast,
         config,
         color: _,
         service,
@@ -98,13 +123,7 @@ pub(crate) fn run(
             problem,
             ..
         },
-        config::Language {
-            src,
-            transpile,
-            compile,
-            run,
-            languageId: language_id,
-        },
+        language,
         base_dir,
     ) = config::target_and_language(
         &cwd,
@@ -120,6 +139,23 @@ pub(crate) fn run(
         },
     )?;
 
+    let resource_limits = language.resource_limits();
+
+    let config::Language {
+        src,
+        transpile,
+        compile,
+        run,
+        languageId: language_id,
+        timelimitMultiplier: timelimit_multiplier,
+        timelimitMarginMs: timelimit_margin_ms,
+        warmUpRuns: warm_up_runs,
+        stackSizeMib: _,
+        addressSpaceMib: _,
+        openFiles: _,
+        cpuTimeSecs: _,
+    } = language;
+
     let code = crate::fs::read_to_string(base_dir.join(&src))?;
     let language_id = language_id.with_context(|| "Missing `languageId`")?;
 
@@ -133,6 +169,7 @@ pub(crate) fn run(
                 shell.stdin_process_redirection,
                 shell.stdout_process_redirection,
                 shell.stderr_process_redirection,
+                force_build,
             )?;
         }
     } else {
@@ -151,9 +188,25 @@ pub(crate) fn run(
             transpile,
             compile,
             run,
+            resource_limits,
+            force_build,
+            timelimit_multiplier,
+            timelimit_margin: timelimit_margin_ms.map(Duration::from_millis),
+            warm_up_runs: warm_up_runs.unwrap_or(0),
             test_case_names: testcases.map(|ss| ss.into_iter().collect()),
+            json: false,
+            reports: vec![],
             display_limit,
-        })?;
+            cpu_time,
+            output_limit,
+            jobs,
+            fail_fast: !no_fail_fast,
+            repeat: NonZeroUsize::new(1).unwrap(),
+            near_timelimit: 1.0,
+            sandbox: None,
+            compact_progress_above: Some(100),
+        })?
+        .error_on_fail()?;
     }
 
     let watch_submission = !no_watch;
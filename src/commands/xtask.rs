@@ -30,11 +30,16 @@ pub(crate) fn run(
             },
     } = ctx;
 
-    let config::Script {
-        program,
-        extension,
-        content,
-    } = config::xtask(&cwd, None, &subcommand)?;
+    let (
+        config::Script {
+            program,
+            extension,
+            content,
+            env,
+            cwd: working_dir,
+        },
+        base_dir,
+    ) = config::xtask(&cwd, None, &subcommand)?;
 
     let mut tempfile = tempfile::Builder::new()
         .prefix(&format!("snowchains-xtask-{}", subcommand))
@@ -44,13 +49,20 @@ pub(crate) fn run(
     tempfile.write_all(content.as_ref())?;
     tempfile.flush()?;
 
-    let status = std::process::Command::new(program)
-        .arg(tempfile.path())
+    let mut cmd = std::process::Command::new(program);
+
+    cmd.arg(tempfile.path())
         .args(args)
+        .envs(env.0)
         .stdin(stdin_process_redirection())
         .stdout(stdout_process_redirection())
-        .stderr(stderr_process_redirection())
-        .status()?;
+        .stderr(stderr_process_redirection());
+
+    if let Some(working_dir) = working_dir {
+        cmd.current_dir(config::resolve_cwd(&base_dir, Some(&working_dir)));
+    }
+
+    let status = cmd.status()?;
 
     if !status.success() {
         match status.code() {
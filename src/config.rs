@@ -6,7 +6,7 @@ use itertools::Itertools as _;
 use maplit::hashmap;
 use serde::Deserialize;
 use serde_dhall::{SimpleType, StaticType};
-use snowchains_core::web::PlatformKind;
+use snowchains_core::{judge::ResourceLimits, web::PlatformKind};
 use std::{
     collections::BTreeMap,
     convert::Infallible,
@@ -71,15 +71,24 @@ pub(crate) fn target_and_language(
     Ok((target, language, dir))
 }
 
-pub(crate) fn xtask(cwd: &Path, rel_path: Option<&Path>, name: &str) -> anyhow::Result<Script> {
+pub(crate) fn xtask(
+    cwd: &Path,
+    rel_path: Option<&Path>,
+    name: &str,
+) -> anyhow::Result<(Script, PathBuf)> {
     let path = find_snowchains_dhall(cwd, rel_path)?;
 
-    let xtask = serde_dhall::from_str(&format!("let config = {} in config.xtask", path))
-        .type_annotation(&map_annot(SimpleType::Text, Script::static_type()))
-        .parse::<IndexMap<String, _>>()
+    let parse = |script_type| {
+        serde_dhall::from_str(&format!("let config = {} in config.xtask", path))
+            .type_annotation(&map_annot(SimpleType::Text, script_type))
+            .parse::<IndexMap<String, Script>>()
+    };
+
+    let xtask = parse(Script::static_type())
+        .or_else(|err| parse(Script::without_env_static_type()).map_err(|_| err))
         .with_context(|| format!("Could not evalute `{}`", path))?;
 
-    xtask.get(name).cloned().with_context(|| {
+    let script = xtask.get(name).cloned().with_context(|| {
         format!(
             "No such xtask subcommand: `{}` (found [{}])",
             name,
@@ -87,7 +96,14 @@ pub(crate) fn xtask(cwd: &Path, rel_path: Option<&Path>, name: &str) -> anyhow::
                 .keys()
                 .format_with(", ", |s, f| f(&format_args!("`{}`", s)))
         )
-    })
+    })?;
+
+    let dir = Path::new(&path)
+        .parent()
+        .unwrap_or_else(|| path.as_ref())
+        .to_owned();
+
+    Ok((script, dir))
 }
 
 fn find_snowchains_dhall(cwd: &Path, rel_path: Option<&Path>) -> anyhow::Result<String> {
@@ -250,14 +266,81 @@ in  {{ service = config.detectServiceFromRelativePathSegments relativePathSegmen
 #[derive(Debug, Deserialize, StaticType, Clone)]
 pub(crate) enum Command {
     Args(Vec<String>),
+    Process(Process),
     Script(Script),
+    ScriptProcess(Script),
+}
+
+impl Command {
+    pub(crate) fn env(&self) -> BTreeMap<String, String> {
+        match self {
+            Self::Args(_) => BTreeMap::new(),
+            Self::Process(Process { env, .. })
+            | Self::Script(Script { env, .. })
+            | Self::ScriptProcess(Script { env, .. }) => env.0.clone(),
+        }
+    }
+
+    /// Resolves `cwd` against `base_dir`, the directory of `snowchains.dhall`.
+    pub(crate) fn cwd(&self, base_dir: &Path) -> PathBuf {
+        match self {
+            Self::Args(_) => base_dir.to_owned(),
+            Self::Process(Process { cwd, .. })
+            | Self::Script(Script { cwd, .. })
+            | Self::ScriptProcess(Script { cwd, .. }) => resolve_cwd(base_dir, cwd.as_deref()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, StaticType, Clone)]
+pub(crate) struct Process {
+    pub(crate) args: Vec<String>,
+    #[serde(default)]
+    pub(crate) env: Env,
+    pub(crate) cwd: Option<String>,
 }
 
+/// `Script` or `ScriptProcess`. `Script` has no `env` and `cwd`.
 #[derive(Debug, Deserialize, StaticType, Clone)]
 pub(crate) struct Script {
     pub(crate) program: String,
     pub(crate) extension: String,
     pub(crate) content: String,
+    #[serde(default)]
+    pub(crate) env: Env,
+    #[serde(default)]
+    pub(crate) cwd: Option<String>,
+}
+
+impl Script {
+    fn without_env_static_type() -> SimpleType {
+        SimpleType::Record(hashmap!(
+            "program".to_owned() => SimpleType::Text,
+            "extension".to_owned() => SimpleType::Text,
+            "content".to_owned() => SimpleType::Text,
+        ))
+    }
+}
+
+/// `Map Text Text`.
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(transparent)]
+pub(crate) struct Env(pub(crate) BTreeMap<String, String>);
+
+impl StaticType for Env {
+    fn static_type() -> SimpleType {
+        map_annot(SimpleType::Text, SimpleType::Text)
+    }
+}
+
+pub(crate) fn resolve_cwd(base_dir: &Path, cwd: Option<&str>) -> PathBuf {
+    match cwd {
+        Some(cwd) => {
+            let cwd = Path::new(cwd);
+            base_dir.join(cwd.strip_prefix(".").unwrap_or(cwd))
+        }
+        None => base_dir.to_owned(),
+    }
 }
 
 #[allow(non_snake_case)] // for `StaticType`
@@ -268,9 +351,32 @@ pub(crate) struct Language {
     pub(crate) compile: Option<Compile>,
     pub(crate) run: Command,
     pub(crate) languageId: Option<String>,
+    /// Scales `timelimit` of the test cases.
+    pub(crate) timelimitMultiplier: Option<f64>,
+    /// Added to `timelimit` of the test cases after scaling.
+    pub(crate) timelimitMarginMs: Option<u64>,
+    /// Number of runs of all the test cases before the tests. Their results are discarded.
+    pub(crate) warmUpRuns: Option<u64>,
+    pub(crate) stackSizeMib: Option<u64>,
+    pub(crate) addressSpaceMib: Option<u64>,
+    pub(crate) openFiles: Option<u64>,
+    pub(crate) cpuTimeSecs: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, StaticType)]
+impl Language {
+    pub(crate) fn resource_limits(&self) -> ResourceLimits {
+        let mib = |n: u64| n.saturating_mul(1024 * 1024);
+
+        ResourceLimits {
+            stack_size: self.stackSizeMib.map(mib),
+            address_space: self.addressSpaceMib.map(mib),
+            open_files: self.openFiles,
+            cpu_time: self.cpuTimeSecs,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, StaticType, Clone)]
 pub(crate) struct Compile {
     pub(crate) command: Command,
     pub(crate) output: String,
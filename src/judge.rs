@@ -1,22 +1,39 @@
-use crate::config;
-use anyhow::bail;
+use crate::{
+    commands::judge::ReportFormat,
+    config,
+    shell::{KeystrokeMode, TtyOrPiped},
+};
+use anyhow::{bail, Context as _};
 use az::SaturatingAs as _;
 use human_size::{Byte, Size};
-use indicatif::ProgressDrawTarget;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use itertools::Itertools as _;
-use maplit::btreemap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
 use snowchains_core::{
-    color_spec, judge::CommandExpression, testsuite::TestSuite, web::PlatformKind,
+    color_spec,
+    judge::{
+        BenchmarkOutcome, CommandExpression, JudgeOptions, JudgeOutcome, ResourceLimits,
+        SandboxOptions, Verdict,
+    },
+    testsuite::{Additional, TestSuite},
+    web::PlatformKind,
 };
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
+    env,
     ffi::OsStr,
-    io::Write as _,
+    io::{self, BufRead as _, Read as _, Write as _},
     iter, mem,
+    num::NonZeroUsize,
     ops::Deref,
     path::{Path, PathBuf},
     process::Stdio,
+    sync::mpsc,
+    thread,
+    time::{Duration, SystemTime},
 };
+use tempfile::NamedTempFile;
 use termcolor::{Color, WriteColor};
 
 pub(crate) struct Args<W1, W2> {
@@ -34,13 +51,28 @@ pub(crate) struct Args<W1, W2> {
     pub(crate) transpile: Option<config::Compile>,
     pub(crate) compile: Option<config::Compile>,
     pub(crate) run: config::Command,
+    pub(crate) resource_limits: ResourceLimits,
+    pub(crate) force_build: bool,
+    pub(crate) timelimit_multiplier: Option<f64>,
+    pub(crate) timelimit_margin: Option<Duration>,
+    pub(crate) warm_up_runs: u64,
     pub(crate) test_case_names: Option<HashSet<String>>,
+    pub(crate) json: bool,
+    pub(crate) reports: Vec<(ReportFormat, Option<PathBuf>)>,
     pub(crate) display_limit: Size,
+    pub(crate) cpu_time: bool,
+    pub(crate) output_limit: Option<Size>,
+    pub(crate) jobs: Option<NonZeroUsize>,
+    pub(crate) fail_fast: bool,
+    pub(crate) repeat: NonZeroUsize,
+    pub(crate) near_timelimit: f64,
+    pub(crate) sandbox: Option<SandboxOptions>,
+    pub(crate) compact_progress_above: Option<usize>,
 }
 
-pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Result<()> {
+pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Result<JudgeOutcome> {
     let Args {
-        stdout,
+        mut stdout,
         mut stderr,
         stdin_process_redirection,
         stdout_process_redirection,
@@ -54,22 +86,68 @@ pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Res
         transpile,
         compile,
         run,
+        resource_limits,
+        force_build,
+        timelimit_multiplier,
+        timelimit_margin,
+        warm_up_runs,
         test_case_names,
+        json,
+        reports,
         display_limit,
+        cpu_time,
+        output_limit,
+        jobs,
+        fail_fast,
+        repeat,
+        near_timelimit,
+        sandbox,
+        compact_progress_above,
     } = args;
 
-    let test_suite_dir = base_dir
-        .join(".snowchains")
-        .join("tests")
-        .join(service.to_kebab_case_str())
-        .join(contest.as_deref().unwrap_or(""));
-    let test_suite_path = test_suite_dir.join(problem).with_extension("yml");
+    let (test_suite_dir, test_suite_path) =
+        test_suite_path(&base_dir, service, contest.as_deref(), &problem);
+
+    let timelimit_scaled = timelimit_multiplier.is_some() || timelimit_margin.is_some();
+    let timelimit_multiplier = timelimit_multiplier.unwrap_or(1.0);
+    if !(timelimit_multiplier.is_finite() && timelimit_multiplier > 0.0) {
+        bail!(
+            "`timelimitMultiplier` must be a positive number: {}",
+            timelimit_multiplier,
+        );
+    }
+    let timelimit_margin = timelimit_margin.unwrap_or_default();
+    if !(near_timelimit.is_finite() && near_timelimit > 0.0) {
+        bail!(
+            "`--near-timelimit` must be a positive number: {}",
+            near_timelimit
+        );
+    }
+    let scale = |timelimit: Option<Duration>| {
+        timelimit.map(|t| t.mul_f64(timelimit_multiplier) + timelimit_margin)
+    };
 
     let test_cases = match crate::fs::read_yaml(&test_suite_path)? {
-        TestSuite::Batch(test_sutie) => {
-            test_sutie.load_test_cases(&test_suite_dir, test_case_names)?
+        TestSuite::Batch(test_sutie) => test_sutie
+            .load_test_cases(&test_suite_dir, test_case_names)?
+            .into_iter()
+            .map(|mut test_case| {
+                test_case.timelimit = scale(test_case.timelimit);
+                test_case.into()
+            })
+            .collect::<Vec<_>>(),
+        TestSuite::Interactive(test_suite) => test_suite
+            .load_test_cases(&test_suite_dir, test_case_names)
+            .with_context(|| format!("Invalid test suite: `{}`", test_suite_path.display()))?
+            .into_iter()
+            .map(|mut test_case| {
+                test_case.timelimit = scale(test_case.timelimit);
+                test_case.into()
+            })
+            .collect(),
+        TestSuite::Unsubmittable => {
+            bail!("`{}` is unsubmittable", test_suite_path.display())
         }
-        _ => todo!("currently only `Batch` is supported"),
     };
 
     let redirections = (
@@ -86,7 +164,53 @@ pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Res
                 writeln!(stderr)?;
             }
 
-            build(&mut stderr, &base_dir, &src, action, redirections, msg)?;
+            build(
+                &mut stderr,
+                &base_dir,
+                &src,
+                action,
+                redirections,
+                msg,
+                force_build,
+            )?;
+        }
+    }
+
+    let (cmd, tempfile) = command_expression(run, base_dir, resource_limits)?;
+
+    let options = JudgeOptions {
+        timelimit_on_cpu_time: cpu_time,
+        output_limit: output_limit.map(|s| s.into::<Byte>().value().saturating_as()),
+        jobs,
+        fail_fast,
+        sandbox,
+        compact_progress_above,
+    };
+
+    if warm_up_runs > 0 && !test_cases.is_empty() {
+        if mem::replace(&mut newline, true) {
+            writeln!(stderr)?;
+        }
+        stderr.set_color(color_spec!(Bold))?;
+        write!(stderr, "Warming up...")?;
+        stderr.reset()?;
+        writeln!(
+            stderr,
+            " ({} run{})",
+            warm_up_runs,
+            if warm_up_runs == 1 { "" } else { "s" }
+        )?;
+        stderr.flush()?;
+
+        // Only to fill caches. The results do not matter.
+        for _ in 0..warm_up_runs {
+            snowchains_core::judge::judge(
+                ProgressDrawTarget::hidden(),
+                tokio::signal::ctrl_c,
+                &cmd,
+                &test_cases,
+                options,
+            )?;
         }
     }
 
@@ -99,40 +223,6 @@ pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Res
     writeln!(stderr)?;
     stderr.flush()?;
 
-    let (cmd, tempfile) = match run {
-        config::Command::Args(args) => {
-            let cmd = CommandExpression {
-                program: args.get(0).cloned().unwrap_or_default().into(),
-                args: args.into_iter().skip(1).map(Into::into).collect(),
-                cwd: base_dir,
-                env: btreemap!(),
-            };
-
-            (cmd, None)
-        }
-        config::Command::Script(config::Script {
-            program,
-            extension,
-            content,
-        }) => {
-            let mut tempfile = tempfile::Builder::new()
-                .prefix("snowchains-test")
-                .suffix(&format!(".{}", extension))
-                .tempfile()?;
-
-            tempfile.write_all(content.as_ref())?;
-
-            let cmd = CommandExpression {
-                program: program.into(),
-                args: vec![tempfile.path().into()],
-                cwd: base_dir,
-                env: btreemap!(),
-            };
-
-            (cmd, Some(tempfile))
-        }
-    };
-
     stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
     write!(stderr, "Test file:")?;
     stderr.reset()?;
@@ -148,29 +238,448 @@ pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Res
     stderr.reset()?;
     writeln!(stderr, " {}", cmd.cwd.display())?;
 
+    if timelimit_scaled {
+        stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
+        write!(stderr, "Timelimit:")?;
+        stderr.reset()?;
+        writeln!(
+            stderr,
+            " x{} + {}ms",
+            timelimit_multiplier,
+            timelimit_margin.as_millis(),
+        )?;
+    }
+
     stderr.flush()?;
 
-    let outcome = snowchains_core::judge::judge(
+    let mut outcome = snowchains_core::judge::judge(
         progress_draw_target,
         tokio::signal::ctrl_c,
         &cmd,
         &test_cases,
+        options,
     )?;
 
+    let benchmark = if repeat.get() > 1 {
+        writeln!(stderr)?;
+        stderr.set_color(color_spec!(Bold))?;
+        write!(stderr, "Repeating...")?;
+        stderr.reset()?;
+        writeln!(
+            stderr,
+            " ({} more run{})",
+            repeat.get() - 1,
+            if repeat.get() == 2 { "" } else { "s" },
+        )?;
+        stderr.flush()?;
+
+        let mut runs = vec![outcome];
+        for _ in 1..repeat.get() {
+            runs.push(snowchains_core::judge::judge(
+                ProgressDrawTarget::hidden(),
+                tokio::signal::ctrl_c,
+                &cmd,
+                &test_cases,
+                options,
+            )?);
+        }
+
+        let benchmark = BenchmarkOutcome::new(&test_cases, &runs, near_timelimit);
+        outcome = benchmark.merge();
+        Some(benchmark)
+    } else {
+        None
+    };
+
     if let Some(tempfile) = tempfile {
         tempfile.close()?;
     }
 
-    writeln!(stderr)?;
-    stderr.flush()?;
-    outcome.print_pretty(
-        stdout,
-        Some(display_limit.into::<Byte>().value().saturating_as()),
-    )?;
+    let display_limit = display_limit.into::<Byte>().value().saturating_as();
+
+    let report_name = format!(
+        "{}/{}{}",
+        service.to_kebab_case_str(),
+        contest
+            .as_deref()
+            .map(|c| format!("{}/", c))
+            .unwrap_or_default(),
+        problem,
+    );
+
+    for (format, path) in &reports {
+        let write_report = |wtr: &mut dyn io::Write| match format {
+            ReportFormat::Junit => outcome.write_junit(wtr, &report_name, Some(display_limit)),
+            ReportFormat::Tap => outcome.write_tap(wtr, Some(display_limit)),
+        };
+
+        if let Some(path) = path {
+            let mut report = vec![];
+            write_report(&mut report)?;
+            crate::fs::write(path, report, true)?;
+            writeln!(stderr, "Wrote `{}`", path.display())?;
+            stderr.flush()?;
+        } else {
+            write_report(&mut stdout)?;
+        }
+    }
+
+    let stdout_taken = reports.iter().any(|(_, path)| path.is_none());
+
+    if json {
+        #[derive(Serialize)]
+        struct JsonOutcome<'a, T> {
+            test_file: &'a Path,
+            command: &'a CommandExpression,
+            #[serde(flatten)]
+            outcome: T,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            benchmark: Option<&'a BenchmarkOutcome>,
+        }
+
+        let outcome = JsonOutcome {
+            test_file: &test_suite_path,
+            command: &cmd,
+            outcome: outcome.with_text_limit(Some(display_limit)),
+            benchmark: benchmark.as_ref(),
+        };
+        writeln!(stdout, "{}", serde_json::to_string(&outcome)?)?;
+        stdout.flush()?;
+    } else if !stdout_taken {
+        writeln!(stderr)?;
+        stderr.flush()?;
+
+        let width = if atty::is(atty::Stream::Stdout) {
+            terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width.into())
+        } else {
+            None
+        };
+
+        outcome.print_pretty(&mut stdout, Some(display_limit), width)?;
+
+        if let Some(benchmark) = &benchmark {
+            writeln!(stdout)?;
+            write!(stdout, "{}", benchmark.to_table())?;
+            stdout.flush()?;
+        }
+    }
 
-    outcome.error_on_fail()
+    Ok(outcome)
 }
 
+fn test_suite_path(
+    base_dir: &Path,
+    service: PlatformKind,
+    contest: Option<&str>,
+    problem: &str,
+) -> (PathBuf, PathBuf) {
+    let test_suite_dir = base_dir
+        .join(".snowchains")
+        .join("tests")
+        .join(service.to_kebab_case_str())
+        .join(contest.unwrap_or(""));
+    let test_suite_path = test_suite_dir.join(problem).with_extension("yml");
+    (test_suite_dir, test_suite_path)
+}
+
+/// Lists the files that `judge` reads: the source file, the test file, and the directories in its `extend`.
+pub(crate) fn watched_paths(
+    base_dir: &Path,
+    service: PlatformKind,
+    contest: Option<&str>,
+    problem: &str,
+    src: &str,
+) -> Vec<PathBuf> {
+    let (test_suite_dir, test_suite_path) = test_suite_path(base_dir, service, contest, problem);
+
+    let src = Path::new(src);
+    let mut paths = vec![base_dir.join(src.strip_prefix(".").unwrap_or(src))];
+
+    // The test file may be broken while it is being edited.
+    if let Ok(TestSuite::Batch(test_suite)) = crate::fs::read_yaml(&test_suite_path) {
+        for Additional::Text { path, .. } in &test_suite.extend {
+            let path = Path::new(path);
+            paths.push(test_suite_dir.join(path.strip_prefix(".").unwrap_or(path)));
+        }
+    }
+
+    paths.push(test_suite_path);
+    paths
+}
+
+/// Runs `judge` every time one of `paths` is modified. `paths` is called again after each run, so
+/// that directories newly added to `extend` are watched.
+///
+/// If stdin is a TTY, keystrokes select the test cases to rerun: Enter reruns all of them, `f` the
+/// failed ones, `t` the ones whose names are typed in, and `q` quits. On platforms other than Unix,
+/// each of them is followed by Enter, and names can be typed directly. While waiting, a status line
+/// is drawn in place if stderr is a TTY.
+pub(crate) fn watch<R, W: WriteColor>(
+    mut stderr: W,
+    stdin: &TtyOrPiped<R>,
+    stderr_tty: bool,
+    mut paths: impl FnMut() -> Vec<PathBuf>,
+    mut test_case_names: Option<HashSet<String>>,
+    mut judge: impl FnMut(&mut W, Option<HashSet<String>>) -> anyhow::Result<JudgeOutcome>,
+) -> anyhow::Result<()> {
+    const INTERVAL: Duration = Duration::from_millis(200);
+
+    #[cfg(unix)]
+    const KEYS: &str = "Enter: all, f: failures, t: select, q: quit";
+    #[cfg(not(unix))]
+    const KEYS: &str = "Enter: all, `f`: failures, NAME...: the test cases, `q`: quit";
+
+    enum Input {
+        Key(u8),
+        Names(String),
+    }
+
+    let interactive = matches!(stdin, TtyOrPiped::Tty);
+
+    let keystroke_mode = if interactive {
+        Some(KeystrokeMode::new()?)
+    } else {
+        None
+    };
+
+    let (tx, rx) = mpsc::channel();
+    if interactive {
+        // `stdin` is not locked when it is a TTY.
+        thread::spawn(move || {
+            if cfg!(unix) {
+                for byte in io::stdin().lock().bytes() {
+                    let sent = byte.map_or(false, |b| tx.send(Input::Key(b)).is_ok());
+                    if !sent {
+                        break;
+                    }
+                }
+            } else {
+                for line in io::stdin().lock().lines() {
+                    let input = line.map(|line| {
+                        let line = line.trim();
+                        match line.len() {
+                            0 => Input::Key(b'\n'),
+                            1 => Input::Key(line.as_bytes()[0]),
+                            _ => Input::Names(line.to_owned()),
+                        }
+                    });
+                    let sent = input.map_or(false, |input| tx.send(input).is_ok());
+                    if !sent {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    let new_status = || {
+        let status = ProgressBar::hidden();
+        status
+            .set_style(ProgressStyle::default_spinner().template("{spinner} {prefix:bold} {msg}"));
+        status.set_prefix("Watching for changes...");
+        status.set_message(if interactive { KEYS } else { "" });
+        if stderr_tty {
+            status.set_draw_target(ProgressDrawTarget::stderr());
+            status.enable_steady_tick(100);
+        }
+        status
+    };
+
+    let mut watched_paths = vec![];
+    let mut last_modified = BTreeMap::new();
+    let mut failures = None;
+    let mut rerun = true;
+    let mut status = ProgressBar::hidden();
+
+    loop {
+        if rerun {
+            status.finish_and_clear();
+
+            match judge(&mut stderr, test_case_names.clone()) {
+                Ok(outcome) => {
+                    failures = Some(
+                        outcome
+                            .verdicts
+                            .iter()
+                            .filter(|v| !matches!(v, Verdict::Accepted { .. }))
+                            .flat_map(|v| v.test_case_name().map(ToOwned::to_owned))
+                            .collect::<HashSet<_>>(),
+                    );
+
+                    if let Err(err) = outcome.error_on_fail() {
+                        writeln!(stderr)?;
+                        writeln!(stderr, "{}", err)?;
+                    }
+                }
+                Err(err) => {
+                    failures = None;
+
+                    writeln!(stderr)?;
+                    stderr.set_color(color_spec!(Bold, Fg(Color::Red)))?;
+                    write!(stderr, "Error:")?;
+                    stderr.reset()?;
+                    writeln!(stderr, " {:#}", err)?;
+                }
+            }
+
+            writeln!(stderr)?;
+            if !stderr_tty {
+                writeln!(stderr, "Watching for changes...")?;
+            }
+            stderr.flush()?;
+
+            status = new_status();
+
+            // The test file may have been modified by the build.
+            watched_paths = paths();
+            last_modified = modified_times(&watched_paths);
+
+            rerun = false;
+        }
+
+        let names = match rx.recv_timeout(INTERVAL) {
+            Ok(Input::Key(b'\n')) => Some(None),
+            Ok(Input::Key(b'f')) => match &failures {
+                Some(failures) if !failures.is_empty() => Some(Some(failures.clone())),
+                _ => {
+                    status.set_message(&format!("No failed test cases. ({})", KEYS));
+                    None
+                }
+            },
+            Ok(Input::Key(b't')) => {
+                status.finish_and_clear();
+                write!(stderr, "Test cases: ")?;
+                stderr.flush()?;
+
+                let line = keystroke_mode
+                    .as_ref()
+                    .expect("should be `Some` if `interactive`")
+                    .suspend(|| {
+                        let mut line = vec![];
+                        while let Ok(Input::Key(b)) = rx.recv() {
+                            if b == b'\n' {
+                                break;
+                            }
+                            line.push(b);
+                        }
+                        String::from_utf8_lossy(&line).into_owned()
+                    })?;
+
+                if line.trim().is_empty() {
+                    status = new_status();
+                    None
+                } else {
+                    Some(Some(line.split_whitespace().map(Into::into).collect()))
+                }
+            }
+            Ok(Input::Key(b'q')) => return Ok(()),
+            Ok(Input::Key(_)) => None,
+            Ok(Input::Names(names)) => {
+                Some(Some(names.split_whitespace().map(Into::into).collect()))
+            }
+            Err(err) => {
+                if err == mpsc::RecvTimeoutError::Disconnected {
+                    thread::sleep(INTERVAL);
+                }
+                if modified_times(&watched_paths) != last_modified {
+                    // Wait for editors to finish writing.
+                    thread::sleep(INTERVAL);
+                    rerun = true;
+                }
+                None
+            }
+        };
+
+        if let Some(names) = names {
+            test_case_names = names;
+            rerun = true;
+        }
+    }
+}
+
+fn modified_times(paths: &[PathBuf]) -> BTreeMap<PathBuf, SystemTime> {
+    fn walk(path: &Path, acc: &mut BTreeMap<PathBuf, SystemTime>) {
+        match std::fs::metadata(path) {
+            Ok(metadata) if metadata.is_dir() => {
+                for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+                    walk(&entry.path(), acc);
+                }
+            }
+            Ok(metadata) => {
+                if let Ok(modified) = metadata.modified() {
+                    acc.insert(path.to_owned(), modified);
+                }
+            }
+            Err(_) => {}
+        }
+    }
+
+    let mut acc = BTreeMap::new();
+    for path in paths {
+        walk(path, &mut acc);
+    }
+    acc
+}
+
+/// Resolves `run` into a `CommandExpression`. A `Script` is written to a temporary file, which must outlive the command.
+pub(crate) fn command_expression(
+    run: config::Command,
+    base_dir: PathBuf,
+    resource_limits: ResourceLimits,
+) -> anyhow::Result<(CommandExpression, Option<NamedTempFile>)> {
+    let cwd = run.cwd(&base_dir);
+
+    let env = run
+        .env()
+        .into_iter()
+        .map(|(k, v)| (k.into(), v.into()))
+        .collect::<BTreeMap<_, _>>();
+
+    match run {
+        config::Command::Args(args) | config::Command::Process(config::Process { args, .. }) => {
+            let cmd = CommandExpression {
+                program: args.get(0).cloned().unwrap_or_default().into(),
+                args: args.into_iter().skip(1).map(Into::into).collect(),
+                cwd,
+                env,
+                resource_limits,
+            };
+
+            Ok((cmd, None))
+        }
+        config::Command::Script(config::Script {
+            program,
+            extension,
+            content,
+            ..
+        })
+        | config::Command::ScriptProcess(config::Script {
+            program,
+            extension,
+            content,
+            ..
+        }) => {
+            let mut tempfile = tempfile::Builder::new()
+                .prefix("snowchains-test")
+                .suffix(&format!(".{}", extension))
+                .tempfile()?;
+
+            tempfile.write_all(content.as_ref())?;
+
+            let cmd = CommandExpression {
+                program: program.into(),
+                args: vec![tempfile.path().into()],
+                cwd,
+                env,
+                resource_limits,
+            };
+
+            Ok((cmd, Some(tempfile)))
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn transpile(
     stderr: impl WriteColor,
     base_dir: &Path,
@@ -179,6 +688,7 @@ pub(crate) fn transpile(
     stdin_process_redirection: fn() -> Stdio,
     stdout_process_redirection: fn() -> Stdio,
     stderr_process_redirection: fn() -> Stdio,
+    force: bool,
 ) -> anyhow::Result<()> {
     build(
         stderr,
@@ -191,23 +701,22 @@ pub(crate) fn transpile(
             stderr_process_redirection,
         ),
         "Transpiling...",
+        force,
     )
 }
 
-#[allow(clippy::type_complexity)]
-fn build(
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub(crate) fn build(
     mut stderr: impl WriteColor,
     base_dir: &Path,
     src: &str,
     build_action: &config::Compile,
     redirections: (fn() -> Stdio, fn() -> Stdio, fn() -> Stdio),
     msg: &'static str,
+    force: bool,
 ) -> anyhow::Result<()> {
-    let src_modified = {
-        let src = Path::new(&src);
-        let src = base_dir.join(src.strip_prefix(".").unwrap_or(src));
-        crate::fs::metadata(src)?.modified()?
-    };
+    let src = Path::new(&src);
+    let src = base_dir.join(src.strip_prefix(".").unwrap_or(src));
 
     let config::Compile { command, output } = build_action;
 
@@ -217,7 +726,26 @@ fn build(
     let (stdin_process_redirection, stdout_process_redirection, stderr_process_redirection) =
         redirections;
 
-    if output.exists() && crate::fs::metadata(&output)?.modified()? > src_modified {
+    let fingerprints_path = base_dir.join(".snowchains").join("fingerprints.json");
+
+    let mut fingerprints = if fingerprints_path.exists() {
+        crate::fs::read_json::<BTreeMap<PathBuf, BuildFingerprint>, _>(&fingerprints_path)
+            .unwrap_or_default()
+    } else {
+        BTreeMap::new()
+    };
+
+    let hash = build_hash(&src, command, &output)?;
+
+    let up_to_date = match (
+        fingerprints.get(&output),
+        BuildFingerprint::new(hash.clone(), &output),
+    ) {
+        (Some(last), Some(current)) => *last == current,
+        _ => false,
+    };
+
+    if up_to_date && !force {
         writeln!(stderr, "{} is up to date.", output.display())?;
         stderr.flush()?;
     } else {
@@ -240,11 +768,16 @@ fn build(
             }
         }
 
+        let cwd = command.cwd(base_dir);
+        let env = command.env();
+
         match command {
-            config::Command::Args(args) => run_command(
+            config::Command::Args(args)
+            | config::Command::Process(config::Process { args, .. }) => run_command(
                 args.get(0).map(Deref::deref).unwrap_or(""),
                 args.iter().skip(1),
-                &base_dir,
+                &cwd,
+                &env,
                 stdin_process_redirection(),
                 stdout_process_redirection(),
                 stderr_process_redirection(),
@@ -254,6 +787,13 @@ fn build(
                 program,
                 extension,
                 content,
+                ..
+            })
+            | config::Command::ScriptProcess(config::Script {
+                program,
+                extension,
+                content,
+                ..
             }) => {
                 let mut tempfile = tempfile::Builder::new()
                     .prefix("snowchains-test")
@@ -265,7 +805,8 @@ fn build(
                 run_command(
                     program,
                     &[tempfile.path()],
-                    &base_dir,
+                    &cwd,
+                    &env,
                     stdin_process_redirection(),
                     stdout_process_redirection(),
                     stderr_process_redirection(),
@@ -275,15 +816,125 @@ fn build(
                 tempfile.close()?;
             }
         }
+
+        if let Some(fingerprint) = BuildFingerprint::new(hash, &output) {
+            fingerprints.insert(output, fingerprint);
+            crate::fs::write_json(&fingerprints_path, fingerprints, true)?;
+        }
     }
 
     Ok(())
 }
 
+/// A record of a successful build, kept in `.snowchains/fingerprints.json`.
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+struct BuildFingerprint {
+    hash: String,
+    output_modified: SystemTime,
+    output_len: u64,
+}
+
+impl BuildFingerprint {
+    /// Returns `None` if the output does not exist.
+    fn new(hash: String, output: &Path) -> Option<Self> {
+        let metadata = crate::fs::metadata(output).ok()?;
+        Some(Self {
+            hash,
+            output_modified: metadata.modified().ok()?,
+            output_len: metadata.len(),
+        })
+    }
+}
+
+/// Hashes the source file, the command, and the environment variables that affect common
+/// toolchains.
+///
+/// Only `src` itself is read. Changes in the files it depends on, such as headers, other modules,
+/// and `Cargo.toml`, are not detected. Use `--force-build` for them.
+fn build_hash(src: &Path, command: &config::Command, output: &Path) -> anyhow::Result<String> {
+    const TOOLCHAIN_ENV_VARS: &[&str] = &[
+        "PATH",
+        "CC",
+        "CXX",
+        "CFLAGS",
+        "CXXFLAGS",
+        "CPPFLAGS",
+        "LDFLAGS",
+        "CPATH",
+        "C_INCLUDE_PATH",
+        "CPLUS_INCLUDE_PATH",
+        "LIBRARY_PATH",
+        "RUSTC",
+        "RUSTC_WRAPPER",
+        "RUSTFLAGS",
+        "CARGO_TARGET_DIR",
+        "CARGO_BUILD_TARGET",
+        "CARGO_ENCODED_RUSTFLAGS",
+        "RUSTUP_TOOLCHAIN",
+        "GOFLAGS",
+        "JAVA_HOME",
+    ];
+
+    let mut hasher = Sha256::new();
+
+    let mut update = |data: &[u8]| {
+        hasher.update(&(data.len() as u64).to_le_bytes());
+        hasher.update(data);
+    };
+
+    update(crate::fs::read_to_string(src)?.as_ref());
+
+    match command {
+        config::Command::Args(args) | config::Command::Process(config::Process { args, .. }) => {
+            update(b"args");
+            for arg in args {
+                update(arg.as_ref());
+            }
+        }
+        config::Command::Script(config::Script {
+            program,
+            extension,
+            content,
+            ..
+        })
+        | config::Command::ScriptProcess(config::Script {
+            program,
+            extension,
+            content,
+            ..
+        }) => {
+            update(b"script");
+            update(program.as_ref());
+            update(extension.as_ref());
+            update(content.as_ref());
+        }
+    }
+
+    for (key, value) in command.env() {
+        update(key.as_ref());
+        update(value.as_ref());
+    }
+
+    update(command.cwd("".as_ref()).to_string_lossy().as_bytes());
+
+    update(output.to_string_lossy().as_bytes());
+
+    for key in TOOLCHAIN_ENV_VARS {
+        update(key.as_ref());
+        if let Some(value) = env::var_os(key) {
+            update(value.to_string_lossy().as_bytes());
+        }
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[allow(clippy::too_many_arguments)]
 fn run_command<S1: AsRef<OsStr>, S2: AsRef<OsStr>, I: IntoIterator<Item = S2>, W: WriteColor>(
     program: S1,
     args: I,
-    base_dir: &Path,
+    cwd: &Path,
+    env: &BTreeMap<String, String>,
     stdin_process_redirection: Stdio,
     stdout_process_redirection: Stdio,
     stderr_process_redirection: Stdio,
@@ -306,13 +957,14 @@ fn run_command<S1: AsRef<OsStr>, S2: AsRef<OsStr>, I: IntoIterator<Item = S2>, W
     stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
     write!(stderr, "Working Directory:")?;
     stderr.reset()?;
-    writeln!(stderr, " {}", base_dir.display())?;
+    writeln!(stderr, " {}", cwd.display())?;
 
     stderr.flush()?;
 
     let status = std::process::Command::new(program)
         .args(&args)
-        .current_dir(base_dir)
+        .current_dir(cwd)
+        .envs(env)
         .stdin(stdin_process_redirection)
         .stdout(stdout_process_redirection)
         .stderr(stderr_process_redirection)
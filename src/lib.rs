@@ -9,8 +9,8 @@ pub use crate::commands::{
     init::OptInit, judge::OptJudge, login::OptLogin, participate::OptParticipate,
     retrieve_languages::OptRetrieveLanguages,
     retrieve_submission_summaries::OptRetrieveSubmissionSummaries,
-    retrieve_testcases::OptRetrieveTestcases, submit::OptSubmit,
-    watch_submissions::OptWatchSubmissions, xtask::OptXtask,
+    retrieve_testcases::OptRetrieveTestcases, shrink::OptShrink, stress::OptStress,
+    submit::OptSubmit, watch_submissions::OptWatchSubmissions, xtask::OptXtask,
 };
 use std::{env, io::BufRead, path::PathBuf};
 use structopt::{
@@ -54,6 +54,14 @@ pub enum Opt {
     #[structopt(author, visible_alias("s"))]
     Submit(OptSubmit),
 
+    /// Tests code against a reference solution with generated inputs
+    #[structopt(author)]
+    Stress(OptStress),
+
+    /// Shrinks the input of a failing test case
+    #[structopt(author)]
+    Shrink(OptShrink),
+
     /// Runs a custom subcommand written in the config file
     #[structopt(author, visible_alias("x"), setting = AppSettings::TrailingVarArg)]
     Xtask(OptXtask),
@@ -118,7 +126,9 @@ impl Opt {
             | Self::Download(OptRetrieveTestcases { color, .. })
             | Self::Watch(OptWatch::Submissions(OptWatchSubmissions { color, .. }))
             | Self::Judge(OptJudge { color, .. })
-            | Self::Submit(OptSubmit { color, .. }) => color,
+            | Self::Submit(OptSubmit { color, .. })
+            | Self::Stress(OptStress { color, .. })
+            | Self::Shrink(OptShrink { color, .. }) => color,
             Self::Xtask(_) => crate::ColorChoice::Auto,
         }
     }
@@ -154,6 +164,8 @@ pub fn run<R: BufRead, W1: WriteColor, W2: WriteColor>(
         Opt::Watch(OptWatch::Submissions(opt)) => commands::watch_submissions::run(opt, ctx),
         Opt::Judge(opt) => commands::judge::run(opt, ctx),
         Opt::Submit(opt) => commands::submit::run(opt, ctx),
+        Opt::Stress(opt) => commands::stress::run(opt, ctx),
+        Opt::Shrink(opt) => commands::shrink::run(opt, ctx),
         Opt::Xtask(opt) => commands::xtask::run(opt, ctx),
     }
 }
@@ -152,3 +152,77 @@ impl<R: BufRead> TtyOrPiped<R> {
         }
     }
 }
+
+/// Passes each keystroke on stdin without waiting for Enter or echoing it, until dropped.
+///
+/// Output processing and signals such as Ctrl-C are left as they are. On platforms other than Unix,
+/// this does nothing.
+pub(crate) struct KeystrokeMode {
+    #[cfg(unix)]
+    original: libc::termios,
+}
+
+impl KeystrokeMode {
+    pub(crate) fn new() -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            let original = unsafe {
+                let mut termios = std::mem::zeroed();
+                if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                termios
+            };
+
+            let mut termios = original;
+            termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+            termios.c_cc[libc::VMIN] = 1;
+            termios.c_cc[libc::VTIME] = 0;
+            set_termios(&termios)?;
+
+            Ok(Self { original })
+        }
+
+        #[cfg(not(unix))]
+        {
+            Ok(Self {})
+        }
+    }
+
+    /// Restores the original mode while `f` runs, e.g. to read a line.
+    pub(crate) fn suspend<T>(&self, f: impl FnOnce() -> T) -> io::Result<T> {
+        #[cfg(unix)]
+        {
+            let mut keystroke = unsafe { std::mem::zeroed() };
+            if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut keystroke) } == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            set_termios(&self.original)?;
+            let output = f();
+            set_termios(&keystroke)?;
+            Ok(output)
+        }
+
+        #[cfg(not(unix))]
+        {
+            Ok(f())
+        }
+    }
+}
+
+impl Drop for KeystrokeMode {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            let _ = set_termios(&self.original);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn set_termios(termios: &libc::termios) -> io::Result<()> {
+    if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}